@@ -17,10 +17,13 @@ pub struct Board {
     pub squares: [[Square; 8]; 8],
     pub centers: [[Pos2; 8]; 8],
     pub state: State,
+    pub side_to_move: Color,
     pub players: Players,
     pub en_passant_target: Option<(usize, usize)>,
     pub flags: Flags,
     pub halfmove_clock: usize,
+    pub fullmove_number: usize,
+    pub start_fen: Option<String>,
     pub position_history: HashMap<u64, usize>,
     pub moves: Vec<Move>,
 }
@@ -41,6 +44,8 @@ pub enum State {
     Checkmate { winner: Color },
     Stalemate,
     Draw,
+    Timeout { winner: Color },
+    IllegalMove { winner: Color },
 }
 
 impl Default for State {
@@ -51,6 +56,30 @@ impl Default for State {
     }
 }
 
+impl State {
+    pub fn pgn_result(&self) -> &'static str {
+        match self {
+            State::Checkmate { winner }
+            | State::Timeout { winner }
+            | State::IllegalMove { winner } => match winner {
+                Color::White => "1-0",
+                Color::Black => "0-1",
+            },
+            State::Stalemate | State::Draw => "1/2-1/2",
+            State::Playing { .. } => "*",
+        }
+    }
+
+    pub fn termination(&self) -> &'static str {
+        match self {
+            State::Checkmate { .. } | State::Stalemate | State::Draw => "Normal",
+            State::Timeout { .. } => "Time forfeit",
+            State::IllegalMove { .. } => "Rules infraction",
+            State::Playing { .. } => "Unterminated",
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Player {
     pub captures: Vec<PieceKind>,
@@ -91,10 +120,13 @@ impl Default for Board {
             squares,
             centers,
             state: State::default(),
+            side_to_move: STARTING_COLOR,
             players: Players::default(),
             en_passant_target: None,
             flags: Flags::default(),
             halfmove_clock: 0,
+            fullmove_number: 1,
+            start_fen: None,
             position_history: HashMap::new(),
             moves: Vec::new(),
         };
@@ -129,6 +161,7 @@ impl Default for Board {
             board.squares[7][i].piece = Some(front_rank[i]);
         }
 
+        board.record_position();
         board
     }
 }
@@ -139,6 +172,18 @@ impl Board {
         r < 8 && f < 8
     }
 
+    // Counts ranks from the given side's back rank, so 7 is always the promotion rank
+    pub fn relative_rank(rank: usize, color: Color) -> usize {
+        match color {
+            Color::White => 7 - rank,
+            Color::Black => rank,
+        }
+    }
+
+    pub fn square_name((rank, file): (usize, usize)) -> String {
+        format!("{}{}", (b'a' + file as u8) as char, 8 - rank)
+    }
+
     pub fn piece_at(&self, pos: (usize, usize)) -> Option<PieceKind> {
         if Self::is_valid_pos(pos) {
             let (r, f) = pos;
@@ -148,6 +193,14 @@ impl Board {
         }
     }
 
+    pub fn pieces(&self) -> impl Iterator<Item = ((usize, usize), PieceKind)> + '_ {
+        self.squares.iter().enumerate().flat_map(|(rank, row)| {
+            row.iter()
+                .enumerate()
+                .filter_map(move |(file, square)| square.piece.map(|p| ((rank, file), p)))
+        })
+    }
+
     pub fn center_at(&self, (rank, file): (usize, usize)) -> Option<Pos2> {
         if Self::is_valid_pos((rank, file)) {
             Some(self.centers[rank][file])
@@ -194,13 +247,8 @@ impl Board {
             captured = self.validate_no_self_capture(to, piece.color())?;
         }
 
-        let promotion_rank = match piece.color() {
-            Color::White => 0,
-            Color::Black => 7,
-        };
-
         let is_pawn_move = piece.to_type() == PieceType::Pawn;
-        let promoted_piece = if is_pawn_move && to.0 == promotion_rank {
+        let promoted_piece = if is_pawn_move && Self::relative_rank(to.0, piece.color()) == 7 {
             if let Some(prom_piece) = promotion {
                 if prom_piece.color() != piece.color() {
                     return Err("Promotion piece must be same color".into());
@@ -238,6 +286,11 @@ impl Board {
             self.halfmove_clock += 1;
         }
 
+        if piece.color() == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = piece.color().opponent();
+
         let mv = Move {
             from,
             to,
@@ -255,35 +308,34 @@ impl Board {
             _ => return,
         };
 
-        let next_turn = current_turn.opponent();
-
-        let in_check = self.is_in_check(next_turn);
-        let has_moves = self.any_legal_move(next_turn);
+        // Positions before a capture or pawn move can never recur
+        if self.halfmove_clock == 0 {
+            self.position_history.clear();
+        }
+        let num_repeats = self.record_position();
+        self.state = self.resolve_state(current_turn.opponent(), num_repeats);
+    }
 
-        let hash = self.compute_position_hash();
-        let entry = self.position_history.entry(hash).or_insert(0);
-        *entry += 1;
-        let num_repeats = *entry;
+    pub fn resolve_state(&self, turn: Color, num_repeats: usize) -> State {
+        let in_check = self.is_in_check(turn);
+        let has_moves = self.any_legal_move(turn);
 
-        self.state = if !has_moves && in_check {
+        if !has_moves && in_check {
             State::Checkmate {
-                winner: current_turn,
+                winner: turn.opponent(),
             }
         } else if !self.has_sufficient_material() {
             State::Draw
         } else if num_repeats >= 3 || self.halfmove_clock >= 100 {
             State::Draw
         } else if has_moves {
-            State::Playing {
-                turn: current_turn.opponent(),
-            }
+            State::Playing { turn }
         } else {
             State::Stalemate
-        };
+        }
     }
 
     pub fn refresh(&self, rect: Rect) -> Self {
-        use PieceKind::*;
         let square_size = rect.width() / 8.0;
 
         let mut centers = [[Pos2::ZERO; 8]; 8];
@@ -295,48 +347,165 @@ impl Board {
             }
         }
 
-        let mut board = Board {
-            squares: [[Square { piece: None }; 8]; 8],
+        Board {
             centers,
-            state: self.state,
-            players: self.players.clone(),
-            en_passant_target: self.en_passant_target,
-            flags: self.flags,
-            halfmove_clock: self.halfmove_clock,
-            position_history: self.position_history.clone(),
-            moves: self.moves.clone(),
-        };
+            ..self.clone()
+        }
+    }
+}
 
-        for i in 0..8 {
-            board.squares[1][i].piece = Some(BlackPawn);
-            board.squares[6][i].piece = Some(WhitePawn);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(board: &mut Board, moves: &[&str]) {
+        for uci in moves {
+            board.push_uci(uci).unwrap();
         }
-        let back_rank = [
-            BlackRook,
-            BlackKnight,
-            BlackBishop,
-            BlackQueen,
-            BlackKing,
-            BlackBishop,
-            BlackKnight,
-            BlackRook,
-        ];
-        let front_rank = [
-            WhiteRook,
-            WhiteKnight,
-            WhiteBishop,
-            WhiteQueen,
-            WhiteKing,
-            WhiteBishop,
-            WhiteKnight,
-            WhiteRook,
+    }
+
+    #[test]
+    fn pieces_visits_every_occupied_square() {
+        let board = Board::default();
+        assert_eq!(board.pieces().count(), 32);
+        assert_eq!(
+            board
+                .pieces()
+                .filter(|(_, p)| p.color() == Color::White)
+                .count(),
+            16
+        );
+        assert_eq!(
+            board.pieces().next(),
+            Some(((0, 0), board.piece_at((0, 0)).unwrap()))
+        );
+
+        let board = Board::from_fen("4k3/8/8/8/3P4/8/8/4K3 w - - 0 1").unwrap();
+        let squares: Vec<_> = board.pieces().map(|(pos, _)| pos).collect();
+        assert_eq!(squares, [(0, 4), (4, 3), (7, 4)]);
+    }
+
+    #[test]
+    fn pgn_results_and_terminations() {
+        let mate = State::Checkmate {
+            winner: Color::White,
+        };
+        assert_eq!((mate.pgn_result(), mate.termination()), ("1-0", "Normal"));
+
+        let timeout = State::Timeout {
+            winner: Color::Black,
+        };
+        assert_eq!(
+            (timeout.pgn_result(), timeout.termination()),
+            ("0-1", "Time forfeit")
+        );
+
+        let illegal = State::IllegalMove {
+            winner: Color::White,
+        };
+        assert_eq!(
+            (illegal.pgn_result(), illegal.termination()),
+            ("1-0", "Rules infraction")
+        );
+
+        assert_eq!(State::Stalemate.pgn_result(), "1/2-1/2");
+        assert_eq!(State::Draw.pgn_result(), "1/2-1/2");
+
+        let playing = State::Playing { turn: Color::White };
+        assert_eq!(
+            (playing.pgn_result(), playing.termination()),
+            ("*", "Unterminated")
+        );
+    }
+
+    #[test]
+    fn relative_ranks_count_from_each_back_rank() {
+        // Rank index 0 is the eighth rank
+        assert_eq!(Board::relative_rank(7, Color::White), 0);
+        assert_eq!(Board::relative_rank(0, Color::White), 7);
+        assert_eq!(Board::relative_rank(0, Color::Black), 0);
+        assert_eq!(Board::relative_rank(7, Color::Black), 7);
+        assert_eq!(Board::relative_rank(4, Color::White), 3);
+        assert_eq!(Board::relative_rank(4, Color::Black), 4);
+    }
+
+    #[test]
+    fn square_names() {
+        assert_eq!(Board::square_name((0, 0)), "a8");
+        assert_eq!(Board::square_name((7, 7)), "h1");
+        assert_eq!(Board::square_name((4, 4)), "e4");
+    }
+
+    #[test]
+    fn irreversible_moves_trim_repetition_history() {
+        let mut board = Board::default();
+        play(&mut board, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+        assert_eq!(board.position_history.len(), 4);
+
+        play(&mut board, &["e2e4"]);
+        assert_eq!(board.position_history.len(), 1);
+    }
+
+    #[test]
+    fn threefold_repetition_survives_trimming() {
+        let mut board = Board::default();
+        play(&mut board, &["e2e4", "e7e5"]);
+
+        // The position after 1...e5 counts even though its en passant square was set
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+        play(&mut board, &shuffle);
+        play(&mut board, &shuffle[..3]);
+        assert!(matches!(board.state, State::Playing { .. }));
+
+        play(&mut board, &shuffle[3..]);
+        assert!(matches!(board.state, State::Draw));
+    }
+
+    #[test]
+    fn starting_position_counts_toward_repetition() {
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+        let mut board = Board::default();
+        play(&mut board, &shuffle);
+        play(&mut board, &shuffle[..3]);
+        assert!(matches!(board.state, State::Playing { .. }));
+        play(&mut board, &shuffle[3..]);
+        assert!(matches!(board.state, State::Draw));
+
+        let fen = "4k3/8/8/8/8/8/8/R3K3 w - - 0 1";
+        let mut board = Board::from_fen(fen).unwrap();
+        let shuffle = ["a1a2", "e8d8", "a2a1", "d8e8"];
+        play(&mut board, &shuffle);
+        play(&mut board, &shuffle);
+        assert!(matches!(board.state, State::Draw));
+    }
+
+    #[test]
+    fn uncapturable_en_passant_square_does_not_break_repetition() {
+        let moves = [
+            "e4", "e5", "Nf3", "Nc6", "Ng1", "Nb8", "Nf3", "Nc6", "Ng1", "Nb8",
         ];
+        let board = Board::from_startpos_moves(&moves[..9]).unwrap();
+        assert!(matches!(board.state, State::Playing { .. }));
 
-        for i in 0..8 {
-            board.squares[0][i].piece = Some(back_rank[i]);
-            board.squares[7][i].piece = Some(front_rank[i]);
-        }
+        let board = Board::from_startpos_moves(&moves).unwrap();
+        assert!(matches!(board.state, State::Draw));
+    }
 
-        board
+    #[test]
+    fn capturable_en_passant_square_is_part_of_the_position() {
+        let board = Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let without = Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_ne!(
+            board.compute_position_hash(),
+            without.compute_position_hash()
+        );
+
+        let board = Board::from_fen("4k3/8/8/3p4/8/8/8/4K3 w - d6 0 1").unwrap();
+        let without = Board::from_fen("4k3/8/8/3p4/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            board.compute_position_hash(),
+            without.compute_position_hash()
+        );
     }
 }
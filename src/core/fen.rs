@@ -0,0 +1,317 @@
+use crate::core::{
+    Color,
+    board::{Board, Flags},
+    piece::{PieceKind, PieceType},
+};
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+impl Board {
+    pub fn from_fen(fen: &str) -> Result<Board, String> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() != 6 {
+            return Err(format!("Expected 6 FEN fields, found {}", fields.len()));
+        }
+
+        let mut board = Board::default();
+        for rank in 0..8 {
+            for file in 0..8 {
+                board.set_piece((rank, file), None);
+            }
+        }
+
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != 8 {
+            return Err(format!("Expected 8 ranks, found {}", ranks.len()));
+        }
+        for (rank, row) in ranks.iter().enumerate() {
+            let mut file = 0;
+            for c in row.chars() {
+                if let Some(skip) = c.to_digit(10).filter(|n| (1..=8).contains(n)) {
+                    file += skip as usize;
+                    continue;
+                }
+
+                let piece = Self::piece_from_symbol(c)
+                    .ok_or_else(|| format!("Invalid piece symbol `{}`", c))?;
+                if file >= 8 {
+                    return Err(format!("Rank {} has more than 8 squares", 8 - rank));
+                }
+                if piece.to_type() == PieceType::Pawn && (rank == 0 || rank == 7) {
+                    return Err("Pawns cannot be on the first or last rank".into());
+                }
+                board.set_piece((rank, file), Some(piece));
+                file += 1;
+            }
+            if file != 8 {
+                return Err(format!("Rank {} does not have 8 squares", 8 - rank));
+            }
+        }
+
+        for color in [Color::White, Color::Black] {
+            let kings = board
+                .squares
+                .iter()
+                .flatten()
+                .filter(|sq| sq.piece == Some(PieceKind::new(PieceType::King, color)))
+                .count();
+            if kings != 1 {
+                return Err(format!("Expected one {:?} king, found {}", color, kings));
+            }
+        }
+
+        let turn = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            other => return Err(format!("Invalid side to move `{}`", other)),
+        };
+        board.side_to_move = turn;
+        if board.is_in_check(turn.opponent()) {
+            return Err("The side not to move is in check".into());
+        }
+
+        if fields[2] != "-" && !fields[2].chars().all(|c| "KQkq".contains(c)) {
+            return Err(format!("Invalid castling rights `{}`", fields[2]));
+        }
+        // Rights are dropped when the king or rook is no longer on its home square
+        let can_castle = |right: char, king: (usize, usize), rook: (usize, usize)| {
+            let color = if right.is_ascii_uppercase() {
+                Color::White
+            } else {
+                Color::Black
+            };
+            fields[2].contains(right)
+                && board.piece_at(king) == Some(PieceKind::new(PieceType::King, color))
+                && board.piece_at(rook) == Some(PieceKind::new(PieceType::Rook, color))
+        };
+        let flags = Flags {
+            has_white_king_moved: false,
+            has_white_kingside_rook_moved: !can_castle('K', (7, 4), (7, 7)),
+            has_white_queenside_rook_moved: !can_castle('Q', (7, 4), (7, 0)),
+            has_black_king_moved: false,
+            has_black_kingside_rook_moved: !can_castle('k', (0, 4), (0, 7)),
+            has_black_queenside_rook_moved: !can_castle('q', (0, 4), (0, 0)),
+        };
+        board.flags = flags;
+
+        board.en_passant_target = match fields[3] {
+            "-" => None,
+            square => {
+                let (rank, file) = Self::square_from_name(square)
+                    .ok_or_else(|| format!("Invalid en passant square `{}`", square))?;
+                let expected_rank = match turn {
+                    Color::White => 2,
+                    Color::Black => 5,
+                };
+                if rank != expected_rank {
+                    return Err(format!("Invalid en passant square `{}`", square));
+                }
+                Some((rank, file))
+            }
+        };
+
+        board.halfmove_clock = fields[4]
+            .parse()
+            .map_err(|_| format!("Invalid halfmove clock `{}`", fields[4]))?;
+        board.fullmove_number = fields[5]
+            .parse()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| format!("Invalid fullmove number `{}`", fields[5]))?;
+
+        board.position_history.clear();
+        let num_repeats = board.record_position();
+        board.state = board.resolve_state(turn, num_repeats);
+
+        let fen = board.to_fen();
+        if fen != STARTING_FEN {
+            board.start_fen = Some(fen);
+        }
+
+        Ok(board)
+    }
+
+    pub fn to_fen(&self) -> String {
+        format!(
+            "{} {} {}",
+            self.fen_without_counters(),
+            self.halfmove_clock,
+            self.fullmove_number
+        )
+    }
+
+    pub fn fen_without_counters(&self) -> String {
+        let mut placement = String::new();
+        for rank in 0..8 {
+            let mut empty = 0;
+            for file in 0..8 {
+                match self.piece_at((rank, file)) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            placement.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        placement.push(piece.symbol());
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                placement.push_str(&empty.to_string());
+            }
+            if rank < 7 {
+                placement.push('/');
+            }
+        }
+
+        let side = match self.side_to_move {
+            Color::White => "w",
+            Color::Black => "b",
+        };
+
+        let flags = &self.flags;
+        let rights = [
+            (
+                'K',
+                !flags.has_white_king_moved && !flags.has_white_kingside_rook_moved,
+                (7, 7),
+            ),
+            (
+                'Q',
+                !flags.has_white_king_moved && !flags.has_white_queenside_rook_moved,
+                (7, 0),
+            ),
+            (
+                'k',
+                !flags.has_black_king_moved && !flags.has_black_kingside_rook_moved,
+                (0, 7),
+            ),
+            (
+                'q',
+                !flags.has_black_king_moved && !flags.has_black_queenside_rook_moved,
+                (0, 0),
+            ),
+        ];
+        let mut castling: String = rights
+            .iter()
+            .filter(|&&(right, allowed, rook)| {
+                let color = if right.is_ascii_uppercase() {
+                    Color::White
+                } else {
+                    Color::Black
+                };
+                allowed && self.piece_at(rook) == Some(PieceKind::new(PieceType::Rook, color))
+            })
+            .map(|&(right, _, _)| right)
+            .collect();
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let en_passant = self
+            .en_passant_target
+            .map(Board::square_name)
+            .unwrap_or_else(|| "-".into());
+
+        format!("{} {} {} {}", placement, side, castling, en_passant)
+    }
+
+    pub fn square_from_name(name: &str) -> Option<(usize, usize)> {
+        let bytes = name.as_bytes();
+        if bytes.len() != 2 || !(b'a'..=b'h').contains(&bytes[0]) {
+            return None;
+        }
+        if !(b'1'..=b'8').contains(&bytes[1]) {
+            return None;
+        }
+        Some((8 - (bytes[1] - b'0') as usize, (bytes[0] - b'a') as usize))
+    }
+
+    fn piece_from_symbol(symbol: char) -> Option<PieceKind> {
+        let piece_type = match symbol.to_ascii_lowercase() {
+            'p' => PieceType::Pawn,
+            'n' => PieceType::Knight,
+            'b' => PieceType::Bishop,
+            'r' => PieceType::Rook,
+            'q' => PieceType::Queen,
+            'k' => PieceType::King,
+            _ => return None,
+        };
+        let color = if symbol.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        Some(PieceKind::new(piece_type, color))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::board::State;
+
+    #[test]
+    fn start_position_round_trips() {
+        let board = Board::from_fen(STARTING_FEN).unwrap();
+        assert_eq!(board.to_fen(), STARTING_FEN);
+        assert_eq!(Board::default().to_fen(), STARTING_FEN);
+        assert!(board.start_fen.is_none());
+    }
+
+    #[test]
+    fn terminal_position_keeps_side_to_move() {
+        let fen = "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1";
+        let board = Board::from_fen(fen).unwrap();
+        assert!(matches!(board.state, State::Stalemate));
+        assert_eq!(board.to_fen(), fen);
+        assert_eq!(board.start_fen.as_deref(), Some(fen));
+    }
+
+    #[test]
+    fn draws_are_detected_on_load() {
+        let bare_kings = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(matches!(bare_kings.state, State::Draw));
+
+        let fifty_moves = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 100 80").unwrap();
+        assert!(matches!(fifty_moves.state, State::Draw));
+
+        let mated = Board::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 100 80").unwrap();
+        assert!(matches!(
+            mated.state,
+            State::Checkmate {
+                winner: Color::White
+            }
+        ));
+    }
+
+    #[test]
+    fn invalid_fens_are_rejected() {
+        for fen in [
+            "",
+            "8/8/8/8/8/8/8/8 w - - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+            "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e4 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0",
+            "4k2R/8/8/8/8/8/8/4K3 w - - 0 1",
+        ] {
+            assert!(Board::from_fen(fen).is_err(), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn castling_rights_need_pieces_on_home_squares() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/4K2R w KQ - 0 1").unwrap();
+        assert_eq!(board.to_fen(), "4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+    }
+
+    #[test]
+    fn counters_are_left_out_of_position_keys() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 b Q - 12 40").unwrap();
+        assert_eq!(board.fen_without_counters(), "4k3/8/8/8/8/8/8/R3K3 b Q -");
+
+        let fresh = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 b Q - 0 1").unwrap();
+        assert_eq!(fresh.compute_position_hash(), board.compute_position_hash());
+    }
+}
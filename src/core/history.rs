@@ -0,0 +1,215 @@
+use crate::{
+    core::board::{Board, State},
+    moves::moves::Move,
+};
+
+// Snapshots are stored without their move list, which only grows over a game;
+// it is rebuilt from `moves` when a position is revisited
+#[derive(Clone)]
+pub struct History {
+    positions: Vec<Board>,
+    moves: Vec<Move>,
+    sans: Vec<String>,
+    current: usize,
+}
+
+impl History {
+    pub fn new(start: Board) -> Self {
+        let moves = start.moves.clone();
+        Self {
+            positions: vec![Board {
+                moves: Vec::new(),
+                ..start
+            }],
+            moves,
+            sans: Vec::new(),
+            current: 0,
+        }
+    }
+
+    pub fn push(&mut self, board: Board, san: String) {
+        let base = self.base();
+        self.positions.truncate(self.current + 1);
+        self.moves.truncate(base + self.current);
+        self.sans.truncate(self.current);
+
+        self.moves.extend(board.moves.last().cloned());
+        self.positions.push(Board {
+            moves: Vec::new(),
+            ..board
+        });
+        self.sans.push(san);
+        self.current = self.sans.len();
+    }
+
+    pub fn first(&self) -> &Board {
+        &self.positions[0]
+    }
+
+    pub fn latest(&self) -> &Board {
+        &self.positions[self.positions.len() - 1]
+    }
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn sans(&self) -> &[String] {
+        &self.sans
+    }
+
+    pub fn is_at_latest(&self) -> bool {
+        self.current == self.sans.len()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.positions
+            .iter()
+            .any(|board| !matches!(board.state, State::Playing { .. }))
+    }
+
+    // Forfeits end the game without a move, so the last snapshot has to be updated in place
+    pub fn set_latest_state(&mut self, state: State) {
+        if let Some(latest) = self.positions.last_mut() {
+            latest.state = state;
+        }
+    }
+
+    pub fn go_to(&mut self, index: usize) -> Option<Board> {
+        if index >= self.positions.len() || index == self.current {
+            return None;
+        }
+        self.current = index;
+
+        let base = self.base();
+        Some(Board {
+            moves: self.moves[..base + index].to_vec(),
+            ..self.positions[index].clone()
+        })
+    }
+
+    fn base(&self) -> usize {
+        self.moves.len() - (self.positions.len() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Color;
+
+    fn play(history: &mut History, board: &mut Board, uci: &str) {
+        let mv = board.push_uci(uci).unwrap();
+        history.push(board.clone(), mv.to_uci());
+    }
+
+    #[test]
+    fn navigation_restores_positions_and_moves() {
+        let mut board = Board::default();
+        let mut history = History::new(board.clone());
+        for uci in ["e2e4", "e7e5", "g1f3"] {
+            play(&mut history, &mut board, uci);
+        }
+        assert!(history.is_at_latest());
+
+        let start = history.go_to(0).unwrap();
+        assert_eq!(start.to_fen(), Board::default().to_fen());
+        assert!(start.moves.is_empty());
+        assert!(!history.is_at_latest());
+
+        let after_two = history.go_to(2).unwrap();
+        assert_eq!(after_two.move_history_uci(), ["e2e4", "e7e5"]);
+        assert!(history.go_to(2).is_none());
+        assert!(history.go_to(4).is_none());
+
+        let latest = history.go_to(3).unwrap();
+        assert_eq!(latest.to_fen(), board.to_fen());
+        assert_eq!(latest.move_history_uci(), board.move_history_uci());
+    }
+
+    #[test]
+    fn stepping_back_and_forward_matches_the_game() {
+        let mut board = Board::default();
+        let mut history = History::new(board.clone());
+        let mut fens = vec![board.to_fen()];
+        for uci in ["d2d4", "g8f6", "c2c4", "e7e6", "b1c3", "f8b4"] {
+            play(&mut history, &mut board, uci);
+            fens.push(board.to_fen());
+        }
+
+        for index in (0..fens.len() - 1).rev() {
+            assert_eq!(history.go_to(index).unwrap().to_fen(), fens[index]);
+            assert_eq!(history.current(), index);
+        }
+        for (index, fen) in fens.iter().enumerate().skip(1) {
+            assert_eq!(&history.go_to(index).unwrap().to_fen(), fen);
+        }
+        assert!(history.is_at_latest());
+        assert_eq!(history.first().to_fen(), fens[0]);
+    }
+
+    #[test]
+    fn pushing_from_the_past_forks_the_line() {
+        let mut board = Board::default();
+        let mut history = History::new(board.clone());
+        for uci in ["e2e4", "e7e5"] {
+            play(&mut history, &mut board, uci);
+        }
+
+        let mut board = history.go_to(1).unwrap();
+        play(&mut history, &mut board, "c7c5");
+        assert_eq!(history.sans(), ["e2e4", "c7c5"]);
+        assert!(history.is_at_latest());
+
+        let start = history.go_to(0).unwrap();
+        let latest = history.go_to(2).unwrap();
+        assert!(start.moves.is_empty());
+        assert_eq!(latest.move_history_uci(), ["e2e4", "c7c5"]);
+    }
+
+    #[test]
+    fn forfeits_are_kept_in_the_latest_snapshot() {
+        let mut board = Board::default();
+        let mut history = History::new(board.clone());
+        play(&mut history, &mut board, "e2e4");
+        assert!(!history.is_finished());
+
+        history.set_latest_state(State::Timeout {
+            winner: Color::White,
+        });
+        assert!(history.is_finished());
+
+        history.go_to(0).unwrap();
+        let latest = history.go_to(1).unwrap();
+        assert!(matches!(
+            latest.state,
+            State::Timeout {
+                winner: Color::White
+            }
+        ));
+    }
+
+    #[test]
+    fn snapshots_do_not_keep_move_lists() {
+        let mut board = Board::default();
+        let mut history = History::new(board.clone());
+        for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            play(&mut history, &mut board, uci);
+        }
+
+        assert!(history.positions.iter().all(|b| b.moves.is_empty()));
+        assert_eq!(history.moves.len(), 4);
+    }
+
+    #[test]
+    fn history_built_on_played_moves_keeps_them() {
+        let mut board = Board::from_startpos_moves(&["d2d4"]).unwrap();
+        let mut history = History::new(board.clone());
+        play(&mut history, &mut board, "d7d5");
+
+        let start = history.go_to(0).unwrap();
+        assert_eq!(start.move_history_uci(), ["d2d4"]);
+        let latest = history.go_to(1).unwrap();
+        assert_eq!(latest.move_history_uci(), ["d2d4", "d7d5"]);
+    }
+}
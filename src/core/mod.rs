@@ -1,4 +1,6 @@
 pub mod board;
+pub mod fen;
+pub mod history;
 pub mod piece;
 
 pub const STARTING_COLOR: Color = Color::White;
@@ -1,6 +1,6 @@
 use crate::core::Color;
 
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
 
 use eframe::egui::{self, ColorImage, Context, TextureHandle, Vec2};
 use resvg::usvg;
@@ -99,6 +99,10 @@ impl PieceKind {
         }
     }
 
+    pub fn with_type(&self, piece_type: PieceType) -> Self {
+        Self::new(piece_type, self.color())
+    }
+
     pub fn score(&self) -> usize {
         match self.to_type() {
             PieceType::Pawn => 1,
@@ -109,6 +113,28 @@ impl PieceKind {
             PieceType::King => 0,
         }
     }
+
+    pub fn symbol(&self) -> char {
+        let symbol = match self.to_type() {
+            PieceType::Pawn => 'p',
+            PieceType::Knight => 'n',
+            PieceType::Bishop => 'b',
+            PieceType::Rook => 'r',
+            PieceType::Queen => 'q',
+            PieceType::King => 'k',
+        };
+
+        match self.color() {
+            Color::White => symbol.to_ascii_uppercase(),
+            Color::Black => symbol,
+        }
+    }
+}
+
+impl fmt::Display for PieceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
 }
 
 impl<'a> Piece<'a> {
@@ -227,3 +253,22 @@ impl PieceImages {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symbols_follow_fen_case() {
+        assert_eq!(
+            PieceKind::new(PieceType::Knight, Color::White).to_string(),
+            "N"
+        );
+        assert_eq!(
+            PieceKind::new(PieceType::Knight, Color::Black).to_string(),
+            "n"
+        );
+        assert_eq!(PieceKind::new(PieceType::King, Color::White).symbol(), 'K');
+        assert_eq!(PieceKind::new(PieceType::Pawn, Color::Black).symbol(), 'p');
+    }
+}
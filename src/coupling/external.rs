@@ -1,9 +1,14 @@
 use crate::coupling::EngineHandle;
 
-use std::io::{BufRead, BufReader, Read, Write};
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::path::Path;
 use std::process::{ChildStdin, ChildStdout, Command, Stdio};
 use std::sync::mpsc::channel;
 use std::thread;
+use std::time::Duration;
+
+// How long an engine gets to answer `uci` and `isready` before it's given up on
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
 
 pub struct ExternalEngine {
     stdin: ChildStdin,
@@ -11,75 +16,135 @@ pub struct ExternalEngine {
 }
 
 impl ExternalEngine {
-    pub fn spawn_threaded(path: &str) -> std::io::Result<EngineHandle> {
+    pub fn spawn_threaded(path: &str) -> io::Result<EngineHandle> {
+        Self::spawn_with_timeout(path, HANDSHAKE_TIMEOUT)
+    }
+
+    fn spawn_with_timeout(path: &str, timeout: Duration) -> io::Result<EngineHandle> {
         let (cmd_sender, cmd_receiver) = channel::<String>();
         let (response_sender, response_receiver) = channel::<String>();
+        let (name_sender, name_receiver) = channel::<io::Result<Option<String>>>();
 
-        let path = path.to_string();
+        let mut process = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        let stdin = process.stdin.take().expect("Failed to open stdin");
+        let stdout = BufReader::new(process.stdout.take().expect("Failed to open stdout"));
+        let mut engine = Self { stdin, stdout };
 
+        // The handshake runs on the engine thread so that a hung engine can't block the caller
         thread::spawn(move || {
-            let mut engine = ExternalEngine::new(&path).expect("Failed to start engine");
-            engine.start();
+            let name = engine.start();
+            let started = name.is_ok();
+            let _ = name_sender.send(name);
+            if !started {
+                return;
+            }
 
             for cmd in cmd_receiver.iter() {
-                engine.send(&cmd);
+                if engine.send(&cmd).is_err() {
+                    break;
+                }
 
                 if cmd.starts_with("go") {
-                    let lines = engine.read_lines_until("bestmove");
-                    for line in lines {
-                        if line.starts_with("bestmove") {
-                            let _ = response_sender.send(line);
-                            break;
-                        }
+                    let Ok(lines) = engine.read_lines_until("bestmove") else {
+                        break;
+                    };
+                    if let Some(line) = lines.into_iter().find(|l| l.starts_with("bestmove")) {
+                        let _ = response_sender.send(line);
                     }
                 }
             }
         });
 
+        let name = match name_receiver.recv_timeout(timeout) {
+            Ok(Ok(name)) => name,
+            Ok(Err(e)) => {
+                let _ = process.kill();
+                return Err(e);
+            }
+            Err(_) => {
+                // Killing the process closes its output, which frees the engine thread
+                let _ = process.kill();
+                return Err(io::Error::new(
+                    ErrorKind::TimedOut,
+                    "engine did not finish the UCI handshake",
+                ));
+            }
+        };
+        let name = name.unwrap_or_else(|| {
+            Path::new(path)
+                .file_stem()
+                .map_or_else(|| path.to_string(), |s| s.to_string_lossy().into_owned())
+        });
+
         Ok(EngineHandle {
             cmd_sender,
             response_receiver,
+            stale_replies: 0,
+            name,
         })
     }
 
-    fn new(path: &str) -> std::io::Result<Self> {
-        let mut process = Command::new(path)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .spawn()?;
-
-        let stdin = process.stdin.take().expect("Failed to open stdin");
-        let stdout = BufReader::new(process.stdout.take().expect("Failed to open stdout"));
-
-        let mut engine = Self { stdin, stdout };
-        engine.start();
-        Ok(engine)
-    }
-
-    fn send(&mut self, cmd: &str) {
-        writeln!(self.stdin, "{}", cmd).unwrap();
-        self.stdin.flush().unwrap();
+    fn send(&mut self, cmd: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{}", cmd)?;
+        self.stdin.flush()
     }
 
-    fn read_lines_until(&mut self, keyword: &str) -> Vec<String> {
+    fn read_lines_until(&mut self, keyword: &str) -> io::Result<Vec<String>> {
         let mut lines = Vec::new();
         for line in self.stdout.by_ref().lines() {
-            let line = line.unwrap();
-            lines.push(line.clone());
-            if line.contains(keyword) {
-                break;
+            let line = line?;
+            let found = line.contains(keyword);
+            lines.push(line);
+            if found {
+                return Ok(lines);
             }
         }
-        lines
+        Err(io::Error::new(
+            ErrorKind::UnexpectedEof,
+            format!("engine exited before sending `{}`", keyword),
+        ))
     }
 
-    fn start(&mut self) {
-        self.send("uci");
-        self.read_lines_until("uciok");
+    fn start(&mut self) -> io::Result<Option<String>> {
+        self.send("uci")?;
+        let name = self
+            .read_lines_until("uciok")?
+            .iter()
+            .find_map(|line| line.strip_prefix("id name "))
+            .map(|name| name.trim().to_string());
+
+        self.send("setoption name Ponder value false")?;
+
+        self.send("isready")?;
+        self.read_lines_until("readyok")?;
+        Ok(name)
+    }
+}
 
-        self.send("setoption name Ponder value false");
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn engine_that_exits_is_an_error() {
+        let error = ExternalEngine::spawn_with_timeout("true", Duration::from_secs(5)).unwrap_err();
+        assert_ne!(error.kind(), ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn engine_that_never_answers_times_out() {
+        let error =
+            ExternalEngine::spawn_with_timeout("cat", Duration::from_millis(100)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TimedOut);
+    }
 
-        self.send("isready");
-        self.read_lines_until("readyok");
+    #[test]
+    fn missing_engine_is_an_error() {
+        let error = ExternalEngine::spawn_threaded("/nonexistent/engine").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
     }
 }
@@ -4,29 +4,29 @@ use crate::{
         board::{Board, State},
         piece::{PieceKind, PieceType},
     },
+    coupling::EngineHandle,
     gui::launch::Cactus,
     moves::moves::Move,
 };
 
+use std::time::{Duration, Instant};
+
+// Engines are asked for a fixed movetime; this covers startup and process latency
+pub const MOVE_TIME_GRACE: Duration = Duration::from_secs(5);
+
 impl Board {
     pub fn parse_uci_move(&self, uci: &str) -> Option<Move> {
-        let bytes = uci.as_bytes();
-        if bytes.len() < 4 {
+        if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {
             return None;
         }
 
-        let f1 = (bytes[0] - b'a') as usize;
-        let r1 = 8 - (bytes[1] - b'0') as usize;
-        let f2 = (bytes[2] - b'a') as usize;
-        let r2 = 8 - (bytes[3] - b'0') as usize;
-
-        let from = (r1, f1);
-        let to = (r2, f2);
+        let from = Board::square_from_name(&uci[0..2])?;
+        let to = Board::square_from_name(&uci[2..4])?;
 
         let piece = self.piece_at(from)?;
 
-        let promotion = if bytes.len() == 5 {
-            Some(match bytes[4] as char {
+        let promotion = if uci.len() == 5 {
+            Some(match uci.as_bytes()[4] as char {
                 'q' => PieceType::Queen,
                 'r' => PieceType::Rook,
                 'b' => PieceType::Bishop,
@@ -49,62 +49,139 @@ impl Board {
         self.moves.iter().map(|m| m.to_uci()).collect()
     }
 
-    pub fn apply_uci_move(&mut self, uci: &str) -> (Option<PieceKind>, bool) {
-        if let Some(mv) = self.parse_uci_move(uci) {
-            let promotion = mv.promotion.map(|pt| PieceKind::new(pt, mv.piece.color()));
-            match self.move_piece(mv.from, mv.to, promotion) {
-                Ok((_, captured)) => (captured, true),
-                Err(e) => {
-                    eprintln!("Failed to apply UCI move `{}`: {}", uci, e);
-                    (None, false)
-                }
-            }
+    pub fn uci_position_command(&self) -> String {
+        let position = match &self.start_fen {
+            Some(fen) => format!("fen {}", fen),
+            None => "startpos".to_string(),
+        };
+        format!(
+            "position {} moves {}",
+            position,
+            self.move_history_uci().join(" ")
+        )
+    }
+
+    pub fn legal_uci_move(&self, uci: &str) -> Option<(Move, Option<PieceKind>)> {
+        let State::Playing { turn } = self.state else {
+            return None;
+        };
+
+        let mv = self.parse_uci_move(uci)?;
+        let promotion = mv.promotion.map(|pt| mv.piece.with_type(pt));
+        if mv.piece.color() == turn && self.is_move_legal(mv.from, mv.to, promotion) {
+            Some((mv, promotion))
         } else {
-            eprintln!("Invalid UCI move: `{}`", uci);
-            (None, false)
+            None
         }
     }
+
+    pub fn from_startpos_moves(moves: &[&str]) -> Result<Board, String> {
+        let mut board = Board::default();
+        for mv in moves {
+            if board.push_uci(mv).is_err() {
+                board.push_san(mv)?;
+            }
+        }
+        Ok(board)
+    }
+
+    pub fn push_uci(&mut self, uci: &str) -> Result<Move, String> {
+        let (mv, promotion) = self
+            .legal_uci_move(uci)
+            .ok_or_else(|| format!("Illegal move `{}`", uci))?;
+        self.move_piece(mv.from, mv.to, promotion)?;
+        self.update_state();
+        Ok(mv)
+    }
 }
 
 impl Cactus {
     pub fn try_engine_turn(&mut self, thinking_time_ms: usize) {
-        let engine = match self.board.state {
-            State::Playing { turn: Color::White } => self.white_engine.as_ref(),
-            State::Playing { turn: Color::Black } => self.black_engine.as_ref(),
+        if !self.history.is_at_latest() || self.history.is_finished() {
+            return;
+        }
+
+        let turn = match self.board.state {
+            State::Playing { turn } => turn,
             _ => return,
         };
 
         if self.is_engine_turn() && !self.waiting_for_engine_move {
-            if let Some(engine) = engine {
-                let uci_moves = self.board.move_history_uci();
-                let position_cmd = format!("position startpos moves {}", uci_moves.join(" "));
-                engine.send_command(position_cmd);
+            let position = self.board.uci_position_command();
+            if let Some(engine) = self.engine_mut(turn) {
+                engine.send_command(position);
                 engine.send_command(format!("go movetime {thinking_time_ms}"));
 
                 self.waiting_for_engine_move = true;
+                self.engine_deadline = Some(
+                    Instant::now()
+                        + Duration::from_millis(thinking_time_ms as u64)
+                        + MOVE_TIME_GRACE,
+                );
             }
             return;
         }
 
-        if let Some(engine) = engine {
-            if let Some(bestmove_line) = engine.try_receive_response() {
+        let timed_out = self.engine_deadline.is_some_and(|d| Instant::now() > d);
+        let Some(engine) = self.engine_mut(turn) else {
+            return;
+        };
+        match engine.try_receive_response() {
+            Some(bestmove_line) => {
                 if let Some(bestmove) = uci_word(&bestmove_line) {
-                    let result = self.board.apply_uci_move(&bestmove);
-                    match result {
-                        (Some(_), true) => self.capture_sound(),
-                        (None, true) => self.move_sound(),
-                        _ => {}
-                    }
-                    self.board.update_state();
-                    match self.board.state {
-                        State::Checkmate { .. } | State::Stalemate | State::Draw => {
-                            self.handle_game_over();
-                        }
-                        _ => {}
-                    }
                     self.waiting_for_engine_move = false;
+                    self.engine_deadline = None;
+                    self.play_engine_move(turn, &bestmove);
                 }
             }
+            None if timed_out => {
+                engine.abandon_search();
+                eprintln!("Engine playing {:?} did not reply in time", turn);
+                self.forfeit(State::Timeout {
+                    winner: turn.opponent(),
+                });
+            }
+            None => {}
+        }
+    }
+
+    fn play_engine_move(&mut self, turn: Color, bestmove: &str) {
+        match self.board.legal_uci_move(bestmove) {
+            Some((mv, promotion)) => {
+                self.play_move(mv.from, mv.to, promotion);
+            }
+            None => {
+                eprintln!("Engine playing {:?} sent illegal move `{}`", turn, bestmove);
+                self.forfeit(State::IllegalMove {
+                    winner: turn.opponent(),
+                });
+            }
+        }
+    }
+
+    pub fn reset_engines(&mut self) {
+        // A search in progress can't be interrupted, so its reply is skipped instead
+        if self.waiting_for_engine_move
+            && let State::Playing { turn } = self.history.latest().state
+            && let Some(engine) = self.engine_mut(turn)
+        {
+            engine.abandon_search();
+        }
+
+        for engine in [&self.white_engine, &self.black_engine]
+            .into_iter()
+            .flatten()
+        {
+            engine.new_game();
+        }
+        self.waiting_for_engine_move = false;
+        self.engine_deadline = None;
+    }
+
+    fn engine_mut(&mut self, color: Color) -> Option<&mut EngineHandle> {
+        match color {
+            Color::White => self.white_engine.as_mut(),
+            Color::Black => self.black_engine.as_mut(),
         }
     }
 
@@ -125,3 +202,54 @@ pub fn uci_word(line: &str) -> Option<String> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed_uci_moves_are_rejected() {
+        let board = Board::default();
+        for uci in [
+            "", "e2", "e2e", "e2e4qq", "i2i4", "e0e4", "e9e4", "E2E4", "é2e4",
+        ] {
+            assert!(board.parse_uci_move(uci).is_none(), "{uci}");
+        }
+        assert!(board.parse_uci_move("e2e4k").is_none());
+        assert!(board.parse_uci_move("e3e4").is_none());
+    }
+
+    #[test]
+    fn uci_moves_parse_squares_and_promotions() {
+        let board = Board::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let mv = board.parse_uci_move("a7a8n").unwrap();
+        assert_eq!((mv.from, mv.to), ((1, 0), (0, 0)));
+        assert_eq!(mv.promotion, Some(PieceType::Knight));
+        assert!(board.legal_uci_move("a7a8").is_none());
+        assert!(board.legal_uci_move("a7a8n").is_some());
+    }
+
+    #[test]
+    fn startpos_moves_build_the_game() {
+        let board = Board::from_startpos_moves(&["e2e4", "c7c5", "g1f3"]).unwrap();
+        assert_eq!(
+            board.to_fen(),
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+        assert_eq!(board.move_history_uci(), ["e2e4", "c7c5", "g1f3"]);
+        assert_eq!(
+            board.uci_position_command(),
+            "position startpos moves e2e4 c7c5 g1f3"
+        );
+
+        let san = Board::from_startpos_moves(&["e4", "c5", "Nf3"]).unwrap();
+        assert_eq!(san.to_fen(), board.to_fen());
+    }
+
+    #[test]
+    fn startpos_moves_reject_illegal_moves() {
+        assert!(Board::from_startpos_moves(&["e2e4", "e2e4"]).is_err());
+        assert!(Board::from_startpos_moves(&["e2e5"]).is_err());
+        assert!(Board::from_startpos_moves(&["e7e5"]).is_err());
+    }
+}
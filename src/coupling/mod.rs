@@ -1,5 +1,6 @@
 pub mod external;
 pub mod integration;
+pub mod runner;
 
 use std::{
     sync::mpsc::{Receiver, Sender},
@@ -10,6 +11,9 @@ use std::{
 pub struct EngineHandle {
     pub cmd_sender: Sender<String>,
     pub response_receiver: Receiver<String>,
+    // Searches can't be interrupted, so each abandoned one still owes a `bestmove`
+    pub stale_replies: usize,
+    pub name: String,
 }
 
 impl EngineHandle {
@@ -17,9 +21,89 @@ impl EngineHandle {
         let _ = self.cmd_sender.send(cmd);
     }
 
-    pub fn try_receive_response(&self) -> Option<String> {
-        self.response_receiver
-            .recv_timeout(Duration::from_millis(10))
-            .ok()
+    pub fn new_game(&self) {
+        self.send_command("ucinewgame".to_string());
+    }
+
+    pub fn abandon_search(&mut self) {
+        self.stale_replies += 1;
+    }
+
+    pub fn try_receive_response(&mut self) -> Option<String> {
+        while self.stale_replies > 0 {
+            self.response_receiver.try_recv().ok()?;
+            self.stale_replies -= 1;
+        }
+        self.response_receiver.try_recv().ok()
+    }
+
+    pub fn receive_response(&self, timeout: Duration) -> Option<String> {
+        self.response_receiver.recv_timeout(timeout).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::mpsc::channel;
+
+    fn channel_engine() -> (EngineHandle, Receiver<String>, Sender<String>) {
+        let (cmd_sender, cmd_receiver) = channel::<String>();
+        let (response_sender, response_receiver) = channel::<String>();
+        let handle = EngineHandle {
+            cmd_sender,
+            response_receiver,
+            stale_replies: 0,
+            name: "test".to_string(),
+        };
+        (handle, cmd_receiver, response_sender)
+    }
+
+    #[test]
+    fn responses_do_not_block() {
+        let (mut engine, commands, replies) = channel_engine();
+        engine.send_command("isready".to_string());
+        assert_eq!(commands.try_recv().unwrap(), "isready");
+
+        assert!(engine.try_receive_response().is_none());
+        assert!(engine.receive_response(Duration::from_millis(10)).is_none());
+
+        replies.send("bestmove e2e4".to_string()).unwrap();
+        assert_eq!(
+            engine.receive_response(Duration::from_millis(10)).unwrap(),
+            "bestmove e2e4"
+        );
+    }
+
+    #[test]
+    fn abandoned_searches_are_discarded() {
+        let (mut engine, commands, replies) = channel_engine();
+        engine.abandon_search();
+        engine.new_game();
+        assert_eq!(commands.try_recv().unwrap(), "ucinewgame");
+
+        // The abandoned search hasn't answered yet
+        assert!(engine.try_receive_response().is_none());
+        assert_eq!(engine.stale_replies, 1);
+
+        replies.send("bestmove e2e4".to_string()).unwrap();
+        assert!(engine.try_receive_response().is_none());
+        assert_eq!(engine.stale_replies, 0);
+
+        replies.send("bestmove d2d4".to_string()).unwrap();
+        assert_eq!(engine.try_receive_response().unwrap(), "bestmove d2d4");
+    }
+
+    #[test]
+    fn stale_and_fresh_replies_arriving_together() {
+        let (mut engine, _commands, replies) = channel_engine();
+        engine.abandon_search();
+        engine.abandon_search();
+        for reply in ["bestmove a2a3", "bestmove h2h3", "bestmove e2e4"] {
+            replies.send(reply.to_string()).unwrap();
+        }
+        assert_eq!(engine.try_receive_response().unwrap(), "bestmove e2e4");
+        assert!(engine.try_receive_response().is_none());
     }
 }
@@ -0,0 +1,259 @@
+use crate::{
+    core::{
+        Color,
+        board::{Board, State},
+    },
+    coupling::{EngineHandle, integration::uci_word},
+};
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub fn play_game(
+    white: &EngineHandle,
+    black: &EngineHandle,
+    board: Board,
+    movetime_ms: usize,
+    grace: Duration,
+) -> (State, String) {
+    let start = board.clone();
+    let mut board = board;
+    let mut sans = Vec::new();
+    let timeout = Duration::from_millis(movetime_ms as u64) + grace;
+
+    white.new_game();
+    black.new_game();
+
+    while let State::Playing { turn } = board.state {
+        let engine = match turn {
+            Color::White => white,
+            Color::Black => black,
+        };
+
+        engine.send_command(board.uci_position_command());
+        engine.send_command(format!("go movetime {movetime_ms}"));
+
+        let Some(bestmove) = engine
+            .receive_response(timeout)
+            .and_then(|line| uci_word(&line))
+        else {
+            board.state = State::Timeout {
+                winner: turn.opponent(),
+            };
+            break;
+        };
+
+        let before = board.clone();
+        match board.push_uci(&bestmove) {
+            Ok(mv) => sans.push(before.to_san(&mv)),
+            Err(e) => {
+                eprintln!("{:?}: {}", turn, e);
+                board.state = State::IllegalMove {
+                    winner: turn.opponent(),
+                };
+                break;
+            }
+        }
+    }
+
+    let pgn = to_pgn(&start, &sans, board.state, &white.name, &black.name);
+    (board.state, pgn)
+}
+
+fn to_pgn(start: &Board, sans: &[String], state: State, white: &str, black: &str) -> String {
+    let result = state.pgn_result();
+    let mut pgn = String::new();
+    for (tag, value) in [
+        ("Event", "Engine match"),
+        ("Site", "?"),
+        ("Date", &pgn_date()),
+        ("Round", "-"),
+        ("White", white),
+        ("Black", black),
+        ("Result", result),
+        ("Termination", state.termination()),
+    ] {
+        pgn.push_str(&format!("[{} \"{}\"]\n", tag, value));
+    }
+    if let Some(fen) = &start.start_fen {
+        pgn.push_str(&format!("[SetUp \"1\"]\n[FEN \"{}\"]\n", fen));
+    }
+    pgn.push('\n');
+
+    let black_first = start.side_to_move == Color::Black;
+    let mut number = start.fullmove_number;
+    for (i, san) in sans.iter().enumerate() {
+        let white_to_move = (i % 2 == 0) != black_first;
+        if white_to_move {
+            pgn.push_str(&format!("{}. ", number));
+        } else if i == 0 {
+            pgn.push_str(&format!("{}... ", number));
+        }
+        pgn.push_str(san);
+        pgn.push(' ');
+        if !white_to_move {
+            number += 1;
+        }
+    }
+    pgn.push_str(result);
+    pgn
+}
+
+// Today's UTC date as `YYYY.MM.DD`, converted from days since the epoch
+fn pgn_date() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{sync::mpsc::channel, thread};
+
+    // Scripted engines answer at once, so a silent one needn't be waited on for long
+    const GRACE: Duration = Duration::from_millis(250);
+
+    // Answers each `go` with the next scripted move; once the script runs out it
+    // stays silent, like an engine that has hung
+    fn scripted_engine(replies: &[&str]) -> EngineHandle {
+        let (cmd_sender, cmd_receiver) = channel::<String>();
+        let (response_sender, response_receiver) = channel::<String>();
+        let replies: Vec<String> = replies.iter().map(|r| r.to_string()).collect();
+
+        thread::spawn(move || {
+            let mut replies = replies.into_iter();
+            for cmd in cmd_receiver.iter() {
+                if !cmd.starts_with("go") {
+                    continue;
+                }
+                if let Some(reply) = replies.next() {
+                    let _ = response_sender.send(format!("bestmove {}", reply));
+                }
+            }
+        });
+
+        EngineHandle {
+            cmd_sender,
+            response_receiver,
+            stale_replies: 0,
+            name: "scripted".to_string(),
+        }
+    }
+
+    // Replays the movetext of a PGN from its starting position
+    fn replay_pgn(pgn: &str) -> Board {
+        let mut board = pgn
+            .lines()
+            .find_map(|line| line.strip_prefix("[FEN \""))
+            .map_or_else(Board::default, |fen| {
+                Board::from_fen(fen.trim_end_matches("\"]")).unwrap()
+            });
+        let movetext = pgn.lines().filter(|line| !line.starts_with('['));
+        for token in movetext.flat_map(str::split_whitespace) {
+            if token.ends_with('.') || matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*") {
+                continue;
+            }
+            board.push_san(token).unwrap();
+        }
+        board
+    }
+
+    #[test]
+    fn illegal_move_loses() {
+        let white = scripted_engine(&["e2e4", "e4e6"]);
+        let black = scripted_engine(&["e7e5"]);
+        let (state, _) = play_game(&white, &black, Board::default(), 0, GRACE);
+        assert!(matches!(
+            state,
+            State::IllegalMove {
+                winner: Color::Black
+            }
+        ));
+    }
+
+    #[test]
+    fn malformed_move_loses() {
+        let white = scripted_engine(&["0000"]);
+        let black = scripted_engine(&[]);
+        let (state, _) = play_game(&white, &black, Board::default(), 0, GRACE);
+        assert!(matches!(
+            state,
+            State::IllegalMove {
+                winner: Color::Black
+            }
+        ));
+    }
+
+    #[test]
+    fn silent_engine_loses_on_time() {
+        let white = scripted_engine(&["e2e4"]);
+        let black = scripted_engine(&[]);
+        let (state, _) = play_game(&white, &black, Board::default(), 0, GRACE);
+        assert!(matches!(
+            state,
+            State::Timeout {
+                winner: Color::White
+            }
+        ));
+    }
+
+    #[test]
+    fn pgn_has_the_seven_tag_roster() {
+        let white = scripted_engine(&["f2f3", "g2g4"]);
+        let black = scripted_engine(&["e7e5", "d8h4"]);
+        let (state, pgn) = play_game(&white, &black, Board::default(), 0, GRACE);
+        assert!(matches!(
+            state,
+            State::Checkmate {
+                winner: Color::Black
+            }
+        ));
+
+        let tags: Vec<&str> = pgn
+            .lines()
+            .take_while(|line| line.starts_with('['))
+            .map(|line| line[1..].split(' ').next().unwrap())
+            .collect();
+        assert_eq!(
+            tags[..7],
+            ["Event", "Site", "Date", "Round", "White", "Black", "Result"]
+        );
+        assert!(pgn.contains("[White \"scripted\"]"));
+        assert!(pgn.contains("[Result \"0-1\"]"));
+        assert!(pgn.ends_with("1. f3 e5 2. g4 Qh4# 0-1"));
+    }
+
+    #[test]
+    fn pgn_round_trips() {
+        let white = scripted_engine(&["f2f3", "g2g4"]);
+        let black = scripted_engine(&["e7e5", "d8h4"]);
+        let (_, pgn) = play_game(&white, &black, Board::default(), 0, GRACE);
+        let expected = Board::from_startpos_moves(&["f2f3", "e7e5", "g2g4", "d8h4"]).unwrap();
+        assert_eq!(replay_pgn(&pgn).to_fen(), expected.to_fen());
+    }
+
+    #[test]
+    fn pgn_round_trips_from_a_fen() {
+        let fen = "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2";
+        let white = scripted_engine(&[]);
+        let black = scripted_engine(&["d8h4"]);
+        let (_, pgn) = play_game(&white, &black, Board::from_fen(fen).unwrap(), 0, GRACE);
+        assert!(pgn.ends_with("2... Qh4# 0-1"));
+
+        let mut expected = Board::from_fen(fen).unwrap();
+        expected.push_uci("d8h4").unwrap();
+        assert_eq!(replay_pgn(&pgn).to_fen(), expected.to_fen());
+    }
+}
@@ -1,8 +1,14 @@
-use crate::{core::board::Board, gui::launch::Cactus};
+use crate::{
+    core::{
+        board::{Board, State},
+        history::History,
+    },
+    gui::launch::Cactus,
+};
 
 use eframe::{
     Frame,
-    egui::{Context, Pos2, Rect, Response, Sense, Ui, Vec2},
+    egui::{Context, Key, Pos2, Rect, Response, Sense, Ui, Vec2},
 };
 
 impl Cactus {
@@ -18,14 +24,7 @@ impl Cactus {
         if self.board.center_at((0, 0)) == Some(Pos2::ZERO)
             || self.board_size != response.rect.size()
         {
-            let mut new_board = self.board.refresh(response.rect);
-            for rank in 0..8 {
-                for file in 0..8 {
-                    let pos = (rank, file);
-                    new_board.set_piece(pos, self.board.piece_at(pos));
-                }
-            }
-            self.board = new_board;
+            self.board = self.board.refresh(response.rect);
             self.board_size = response.rect.size();
         }
 
@@ -38,6 +37,34 @@ impl Cactus {
             return response;
         }
 
+        if !ctx.wants_keyboard_input() {
+            if ctx.input(|i| i.key_pressed(Key::F)) {
+                self.flipped = !self.flipped;
+            }
+            if ctx.input(|i| i.key_pressed(Key::M)) {
+                self.show_move_list = !self.show_move_list;
+            }
+
+            let current = self.history.current();
+            if ctx.input(|i| i.key_pressed(Key::ArrowLeft)) && current > 0 {
+                self.navigate(current - 1);
+            }
+            if ctx.input(|i| i.key_pressed(Key::ArrowRight)) {
+                self.navigate(current + 1);
+            }
+            if ctx.input(|i| i.key_pressed(Key::Home)) {
+                self.navigate(0);
+            }
+            if ctx.input(|i| i.key_pressed(Key::End)) {
+                self.navigate(self.history.sans().len());
+            }
+        }
+
+        // Past positions are view-only until the latest one is shown again
+        if !self.history.is_at_latest() {
+            return response;
+        }
+
         let pointer = ctx.input(|i| i.pointer.clone());
         if let Some(pos) = pointer.interact_pos() {
             if pointer.primary_pressed() {
@@ -58,6 +85,10 @@ impl Cactus {
             }
         }
 
+        if self.dragging.is_none() {
+            self.update_legal_targets(self.selected);
+        }
+
         response
     }
 
@@ -74,22 +105,50 @@ impl Cactus {
         let col = ((pos.x - board_rect.left()) / square_size).floor() as usize;
         let row = ((pos.y - board_rect.top()) / square_size).floor() as usize;
         if Board::is_valid_pos((row, col)) {
-            Some((row, col))
+            Some(Self::orient((row, col), self.flipped))
         } else {
             None
         }
     }
 
+    pub fn orient((rank, file): (usize, usize), flipped: bool) -> (usize, usize) {
+        if flipped {
+            (7 - rank, 7 - file)
+        } else {
+            (rank, file)
+        }
+    }
+
     pub fn reset_game(&mut self) {
-        self.board = Board::default();
+        self.start_game(Board::default());
+    }
+
+    pub fn start_game(&mut self, board: Board) {
+        self.reset_engines();
+        self.history = History::new(board.clone());
+        self.board = board;
         self.board_size = Vec2::splat(400.0);
         self.dragging = None;
         self.drag_pos = Pos2::default();
         self.selected = None;
+        self.legal_targets.clear();
+        self.legal_targets_from = None;
         self.clear_selection = false;
         self.painter = None;
         self.size = Vec2::default();
         self.promotion_pending = None;
         self.show_game_over_popup = false;
+        self.fen_error = None;
+
+        if !matches!(self.board.state, State::Playing { .. }) {
+            self.handle_game_over();
+        }
+    }
+
+    pub fn load_fen(&mut self) {
+        match Board::from_fen(self.fen_input.trim()) {
+            Ok(board) => self.start_game(board),
+            Err(e) => self.fen_error = Some(e),
+        }
     }
 }
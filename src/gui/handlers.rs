@@ -1,6 +1,10 @@
 use crate::{
-    core::{board::State, piece::PieceKind},
+    core::{
+        board::{Board, State},
+        piece::{PieceKind, PieceType},
+    },
     gui::launch::Cactus,
+    moves::moves::Move,
 };
 
 use eframe::egui::{Pos2, Response};
@@ -14,6 +18,7 @@ impl Cactus {
                         return;
                     }
                 }
+                self.update_legal_targets(Some((rank, file)));
                 self.dragging = Some((piece_kind, rank, file));
                 self.drag_pos = pos;
                 self.board.set_piece((rank, file), None);
@@ -36,11 +41,8 @@ impl Cactus {
             self.board
                 .set_piece((orig_rank, orig_file), Some(piece_kind));
 
-            let needs_promotion = match piece_kind {
-                PieceKind::WhitePawn if target_rank == 0 => true,
-                PieceKind::BlackPawn if target_rank == 7 => true,
-                _ => false,
-            };
+            let needs_promotion = piece_kind.to_type() == PieceType::Pawn
+                && Board::relative_rank(target_rank, piece_kind.color()) == 7;
 
             let (from, to) = ((orig_rank, orig_file), (target_rank, target_file));
             if needs_promotion && self.board.validate_pawn_move(piece_kind.color(), from, to) {
@@ -48,49 +50,8 @@ impl Cactus {
                 return;
             }
 
-            if self
-                .board
-                .is_move_legal((orig_rank, orig_file), (target_rank, target_file), None)
-            {
-                match self.board.move_piece(
-                    (orig_rank, orig_file),
-                    (target_rank, target_file),
-                    None,
-                ) {
-                    Ok((_, captured)) => {
-                        match captured {
-                            Some(_) => self.capture_sound(),
-                            None => self.move_sound(),
-                        }
-
-                        self.board.update_state();
-                        match self.board.state {
-                            State::Checkmate { .. } | State::Stalemate | State::Draw => {
-                                self.handle_game_over();
-                                return;
-                            }
-                            _ => {}
-                        }
-
-                        if let Some(center) = self.board.center_at((target_rank, target_file)) {
-                            self.drag_pos = center;
-                        }
-                        self.selected = None;
-                    }
-                    Err(_) => {
-                        self.board
-                            .set_piece((orig_rank, orig_file), Some(piece_kind));
-                        if let Some(center) = self.board.center_at((orig_rank, orig_file)) {
-                            self.drag_pos = center;
-                        }
-                    }
-                }
-            } else {
-                self.board
-                    .set_piece((orig_rank, orig_file), Some(piece_kind));
-                if let Some(center) = self.board.center_at((orig_rank, orig_file)) {
-                    self.drag_pos = center;
-                }
+            if self.board.is_move_legal(from, to, None) {
+                self.play_move(from, to, None);
             }
 
             self.drag_pos = self.board.centers[target_rank][target_file];
@@ -132,11 +93,8 @@ impl Cactus {
                         if can_move {
                             if self.board.piece_at((sel_rank, sel_file)).is_some() {
                                 let piece_kind = self.board.piece_at((sel_rank, sel_file)).unwrap();
-                                let needs_promotion = match piece_kind {
-                                    PieceKind::WhitePawn if rank == 0 => true,
-                                    PieceKind::BlackPawn if rank == 7 => true,
-                                    _ => false,
-                                };
+                                let needs_promotion = piece_kind.to_type() == PieceType::Pawn
+                                    && Board::relative_rank(rank, piece_kind.color()) == 7;
 
                                 let (from, to) = ((sel_rank, sel_file), (rank, file));
                                 if needs_promotion
@@ -145,34 +103,11 @@ impl Cactus {
                                     self.promotion_pending = Some((from, to));
                                     return;
                                 }
-                                if self.board.is_move_legal(
-                                    (sel_rank, sel_file),
-                                    (rank, file),
-                                    None,
-                                ) {
-                                    if let Ok((_, captured)) = self.board.move_piece(
-                                        (sel_rank, sel_file),
-                                        (rank, file),
-                                        None,
-                                    ) {
-                                        match captured {
-                                            Some(_) => self.capture_sound(),
-                                            None => self.move_sound(),
-                                        }
-                                        self.board.update_state();
-                                        match self.board.state {
-                                            State::Checkmate { .. }
-                                            | State::Stalemate
-                                            | State::Draw => {
-                                                self.handle_game_over();
-                                                return;
-                                            }
-                                            _ => {}
-                                        }
-
-                                        self.drag_pos = self.board.centers[rank][file];
-                                        self.clear_selection = true;
-                                    }
+                                if self.board.is_move_legal(from, to, None)
+                                    && self.play_move(from, to, None)
+                                {
+                                    self.drag_pos = self.board.centers[rank][file];
+                                    self.clear_selection = true;
                                 }
                             }
                         } else {
@@ -191,6 +126,80 @@ impl Cactus {
         }
     }
 
+    pub fn play_move(
+        &mut self,
+        from: (usize, usize),
+        to: (usize, usize),
+        promotion: Option<PieceKind>,
+    ) -> bool {
+        let Some(piece) = self.board.piece_at(from) else {
+            return false;
+        };
+        let mv = Move {
+            from,
+            to,
+            promotion: promotion.map(|p| p.to_type()),
+            piece,
+        };
+        let san = self.board.to_san(&mv);
+        let quiet = mv.is_quiet(&self.board);
+
+        let Ok((_, captured)) = self.board.move_piece(from, to, promotion) else {
+            return false;
+        };
+        match captured {
+            Some(_) => self.capture_sound(),
+            None if quiet => self.move_sound(),
+            None => self.confirmation_sound(),
+        }
+
+        self.board.update_state();
+        self.history.push(self.board.clone(), san);
+        if !matches!(self.board.state, State::Playing { .. }) {
+            self.handle_game_over();
+        }
+
+        true
+    }
+
+    pub fn forfeit(&mut self, state: State) {
+        self.board.state = state;
+        self.history.set_latest_state(state);
+        self.waiting_for_engine_move = false;
+        self.engine_deadline = None;
+        self.handle_game_over();
+    }
+
+    pub fn navigate(&mut self, index: usize) {
+        if let Some(board) = self.history.go_to(index) {
+            self.board = board;
+            self.dragging = None;
+            self.selected = None;
+            self.promotion_pending = None;
+            self.legal_targets.clear();
+            self.legal_targets_from = None;
+        }
+    }
+
+    pub fn update_legal_targets(&mut self, focus: Option<(usize, usize)>) {
+        let movable = focus.filter(|&pos| match self.board.state {
+            State::Playing { turn } => self
+                .board
+                .piece_at(pos)
+                .is_some_and(|piece| piece.color() == turn),
+            _ => false,
+        });
+
+        match movable {
+            Some(pos) if self.legal_targets_from != Some(pos) => {
+                self.legal_targets = self.board.legal_moves_from(pos);
+            }
+            Some(_) => {}
+            None => self.legal_targets.clear(),
+        }
+        self.legal_targets_from = movable;
+    }
+
     pub fn handle_game_over(&mut self) {
         self.game_over_sound();
         self.show_game_over_popup = true;
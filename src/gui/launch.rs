@@ -1,6 +1,9 @@
-use crate::core::{board::*, piece::*};
+use crate::core::{board::*, history::History, piece::*};
 use crate::coupling::EngineHandle;
 use crate::gui::{DEFAULT_BOARD_SIZE, DEFAULT_PIECE_SIZE};
+use crate::moves::moves::Move;
+
+use std::time::Instant;
 
 use eframe::egui::{self, Color32, Context, IconData, Painter, Pos2, Vec2};
 use eframe::{App, Frame};
@@ -15,16 +18,24 @@ pub struct Cactus {
     pub dragging: Option<(PieceKind, usize, usize)>,
     pub drag_pos: Pos2,
     pub selected: Option<(usize, usize)>,
+    pub legal_targets: Vec<Move>,
+    pub legal_targets_from: Option<(usize, usize)>,
     pub clear_selection: bool,
     pub painter: Option<Painter>,
     pub size: Vec2,
     pub audio_stream: Option<OutputStream>,
     pub promotion_pending: Option<((usize, usize), (usize, usize))>,
     pub show_game_over_popup: bool,
+    pub flipped: bool,
+    pub history: History,
+    pub show_move_list: bool,
+    pub fen_input: String,
+    pub fen_error: Option<String>,
 
     pub white_engine: Option<EngineHandle>,
     pub black_engine: Option<EngineHandle>,
     pub waiting_for_engine_move: bool,
+    pub engine_deadline: Option<Instant>,
 }
 
 impl Cactus {
@@ -44,16 +55,24 @@ impl Cactus {
             dragging: None,
             drag_pos: Pos2::default(),
             selected: None,
+            legal_targets: Vec::new(),
+            legal_targets_from: None,
             clear_selection: false,
             painter: None,
             size: Vec2::default(),
             audio_stream: Some(handle),
             promotion_pending: None,
             show_game_over_popup: false,
+            flipped: white_engine.is_some() && black_engine.is_none(),
+            history: History::new(Board::default()),
+            show_move_list: false,
+            fen_input: String::new(),
+            fen_error: None,
 
             white_engine: white_engine,
             black_engine: black_engine,
             waiting_for_engine_move: false,
+            engine_deadline: None,
         }
     }
 }
@@ -1,6 +1,6 @@
 use eframe::egui::{
-    Align, Align2, Color32, Context, CornerRadius, FontId, Frame, ImageButton, Layout, Pos2, Rect,
-    Response, RichText, Vec2, Window, vec2,
+    Align, Align2, Color32, Context, CornerRadius, FontId, Frame, Grid, ImageButton, Layout, Pos2,
+    Rect, Response, RichText, ScrollArea, Stroke, StrokeKind, TextEdit, Vec2, Window, vec2,
 };
 
 use crate::{
@@ -24,11 +24,23 @@ impl Cactus {
         let font_id = FontId::proportional(14.0);
         let text_color = Color32::from_gray(30);
         let padding = 4.0;
+        let flipped = self.flipped;
+        let last_move = self.board.moves.last().map(|mv| (mv.from, mv.to));
+        let checked = match self.board.state {
+            State::Playing { turn } => Some(turn),
+            State::Checkmate { winner } => Some(winner.opponent()),
+            _ => None,
+        };
+        let (checked_king, checkers) = match checked {
+            Some(color) => (self.board.find_king(color), self.board.checkers(color)),
+            None => (None, Vec::new()),
+        };
 
         for rank in 0..8 {
             for file in 0..8 {
-                let x = rect.left() + file as f32 * square_size;
-                let y = rect.top() + rank as f32 * square_size;
+                let (row, col) = Self::orient((rank, file), flipped);
+                let x = rect.left() + col as f32 * square_size;
+                let y = rect.top() + row as f32 * square_size;
                 let square_rect = Rect::from_min_size(Pos2::new(x, y), Vec2::splat(square_size));
 
                 let is_light = (rank + file) % 2 == 0;
@@ -42,7 +54,7 @@ impl Cactus {
                     },
                 );
 
-                if rank == 7 {
+                if row == 7 {
                     let file_char = (b'a' + file as u8) as char;
                     painter.text(
                         square_rect.left_bottom() + vec2(padding, -padding),
@@ -53,7 +65,7 @@ impl Cactus {
                     );
                 }
 
-                if file == 0 {
+                if col == 0 {
                     let rank_num = (8 - rank).to_string();
                     painter.text(
                         square_rect.left_top() + vec2(padding, padding),
@@ -64,6 +76,30 @@ impl Cactus {
                     );
                 }
 
+                if last_move.is_some_and(|(from, to)| from == (rank, file) || to == (rank, file)) {
+                    painter.rect_filled(
+                        square_rect,
+                        0.0,
+                        Color32::from_rgba_unmultiplied(155, 199, 0, 90),
+                    );
+                }
+
+                if !checkers.is_empty() && checked_king == Some((rank, file)) {
+                    painter.rect_filled(
+                        square_rect,
+                        0.0,
+                        Color32::from_rgba_unmultiplied(220, 40, 40, 140),
+                    );
+                }
+                if checkers.contains(&(rank, file)) {
+                    painter.rect_stroke(
+                        square_rect.shrink(1.5),
+                        0.0,
+                        Stroke::new(3.0, Color32::from_rgba_unmultiplied(220, 40, 40, 160)),
+                        StrokeKind::Inside,
+                    );
+                }
+
                 if self.dragging.is_none() && !self.clear_selection {
                     if let Some((sel_rank, sel_file)) = self.selected {
                         if sel_rank == rank && sel_file == file {
@@ -76,6 +112,19 @@ impl Cactus {
                     }
                 }
 
+                if let Some(mv) = self.legal_targets.iter().find(|mv| mv.to == (rank, file)) {
+                    let hint_color = Color32::from_rgba_unmultiplied(0, 0, 0, 50);
+                    if mv.is_capture(&self.board) {
+                        painter.circle_stroke(
+                            square_rect.center(),
+                            square_size * 0.44,
+                            Stroke::new(square_size * 0.08, hint_color),
+                        );
+                    } else {
+                        painter.circle_filled(square_rect.center(), square_size * 0.15, hint_color);
+                    }
+                }
+
                 if let Some(kind) = self.board.piece_at((rank, file)) {
                     if let Some((_, drag_rank, drag_file)) = self.dragging {
                         if drag_rank == rank && drag_file == file {
@@ -84,10 +133,7 @@ impl Cactus {
                     }
 
                     let texture = self.images.get_texture(kind);
-                    let center = self
-                        .board
-                        .center_at((rank, file))
-                        .expect("Position out of bounds");
+                    let center = square_rect.center();
                     let texture_size = texture.size_vec2();
                     let scale = (square_size * 0.9) / texture_size.x.min(texture_size.y);
                     let image_size = texture_size * scale;
@@ -120,12 +166,100 @@ impl Cactus {
             );
         }
 
+        if self.show_move_list {
+            self.render_move_list(ctx);
+        }
         self.render_promotion_popup(ctx);
         if self.show_game_over_popup {
             self.render_game_over_popup(ctx);
         }
     }
 
+    pub fn render_move_list(&mut self, ctx: &Context) {
+        let mut load = false;
+        Window::new("Moves")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::RIGHT_TOP, [-8.0, 8.0])
+            .show(ctx, |ui| {
+                let current = self.history.current();
+                let latest = self.history.sans().len();
+                ui.horizontal(|ui| {
+                    if ui.button("<<").clicked() {
+                        self.navigate(0);
+                    }
+                    if ui.button("<").clicked() && current > 0 {
+                        self.navigate(current - 1);
+                    }
+                    if ui.button(">").clicked() {
+                        self.navigate(current + 1);
+                    }
+                    if ui.button(">>").clicked() {
+                        self.navigate(latest);
+                    }
+                });
+
+                let first = self.history.first();
+                let skipped = match first.side_to_move {
+                    Color::Black => 1,
+                    Color::White => 0,
+                };
+                let first_number = first.fullmove_number;
+                let sans = self.history.sans();
+
+                let mut clicked = None;
+                ScrollArea::vertical()
+                    .max_height(240.0)
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        Grid::new("move_list")
+                            .num_columns(3)
+                            .min_col_width(48.0)
+                            .show(ui, |ui| {
+                                let slots = skipped + sans.len();
+                                for row in 0..slots.div_ceil(2) {
+                                    ui.label(format!("{}.", first_number + row));
+                                    for slot in (2 * row..2 * row + 2).take_while(|&s| s < slots) {
+                                        if slot < skipped {
+                                            ui.label("...");
+                                            continue;
+                                        }
+
+                                        let index = slot - skipped + 1;
+                                        let san = &sans[index - 1];
+                                        if ui.selectable_label(index == current, san).clicked() {
+                                            clicked = Some(index);
+                                        }
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.add(TextEdit::singleline(&mut self.fen_input).hint_text("FEN"));
+                    if ui.button("Load").clicked() {
+                        load = true;
+                    }
+                });
+                if ui.button("Copy FEN").clicked() {
+                    ui.ctx().copy_text(self.board.to_fen());
+                }
+                if let Some(error) = &self.fen_error {
+                    ui.label(RichText::new(error).color(Color32::LIGHT_RED));
+                }
+
+                if let Some(index) = clicked {
+                    self.navigate(index);
+                }
+            });
+
+        if load {
+            self.load_fen();
+        }
+    }
+
     pub fn render_promotion_popup(&mut self, ctx: &Context) {
         if let Some(((from_r, from_f), (to_r, to_f))) = self.promotion_pending {
             let color = self.board.piece_at((from_r, from_f)).unwrap().color();
@@ -152,34 +286,11 @@ impl Cactus {
                             let image_button = ImageButton::new(texture_id);
 
                             if ui.add(image_button).clicked() {
-                                if self.board.is_move_legal(
-                                    (from_r, from_f),
-                                    (to_r, to_f),
-                                    Some(piece_kind),
-                                ) {
-                                    if let Ok((_, captured)) = self.board.move_piece(
-                                        (from_r, from_f),
-                                        (to_r, to_f),
-                                        Some(piece_kind),
-                                    ) {
-                                        match captured {
-                                            Some(_) => self.capture_sound(),
-                                            None => self.confirmation_sound(),
-                                        }
-                                        self.board.update_state();
-
-                                        match self.board.state {
-                                            State::Checkmate { .. }
-                                            | State::Stalemate
-                                            | State::Draw => {
-                                                self.handle_game_over();
-                                                return;
-                                            }
-                                            _ => {}
-                                        }
-
-                                        self.drag_pos = self.board.centers[to_r][to_f];
-                                    }
+                                let (from, to) = ((from_r, from_f), (to_r, to_f));
+                                if self.board.is_move_legal(from, to, Some(piece_kind))
+                                    && self.play_move(from, to, Some(piece_kind))
+                                {
+                                    self.drag_pos = self.board.centers[to_r][to_f];
                                 }
 
                                 self.promotion_pending = None;
@@ -196,16 +307,18 @@ impl Cactus {
             return;
         }
 
+        let wins = |winner| {
+            Some(match winner {
+                Color::White => "White Wins",
+                Color::Black => "Black Wins",
+            })
+        };
         let (title, subtitle) = match self.board.state {
-            State::Checkmate { winner } => (
-                "Checkmate",
-                Some(match winner {
-                    Color::White => "White Wins",
-                    Color::Black => "Black Wins",
-                }),
-            ),
+            State::Checkmate { winner } => ("Checkmate", wins(winner)),
             State::Stalemate => ("Stalemate", None),
             State::Draw => ("Draw", None),
+            State::Timeout { winner } => ("Time Forfeit", wins(winner)),
+            State::IllegalMove { winner } => ("Illegal Move", wins(winner)),
             _ => return,
         };
 
@@ -1,4 +1,9 @@
-use crate::coupling::{EngineHandle, external::ExternalEngine};
+use crate::{
+    core::board::Board,
+    coupling::{EngineHandle, external::ExternalEngine, integration::MOVE_TIME_GRACE, runner},
+};
+
+use std::time::Instant;
 
 mod core;
 mod coupling;
@@ -11,27 +16,93 @@ fn main() {
         .map(|s| s.trim().to_string())
         .collect();
 
+    if args.first().is_some_and(|a| a == "perft") {
+        run_perft(&args[1..]);
+        return;
+    }
+
     let mut maybe_white_engine: Option<EngineHandle> = None;
     let mut maybe_black_engine: Option<EngineHandle> = None;
+    let mut headless = false;
+    let mut movetime_ms = 1000;
 
     let mut i = 0;
     while i < args.len() {
         match args[i].as_str() {
             "white" if i + 1 < args.len() => {
                 let path = &args[i + 1];
-                maybe_white_engine = ExternalEngine::spawn_threaded(path).ok();
+                maybe_white_engine = ExternalEngine::spawn_threaded(path)
+                    .map_err(|e| eprintln!("Failed to start engine `{}`: {}", path, e))
+                    .ok();
                 i += 2;
             }
             "black" if i + 1 < args.len() => {
                 let path = &args[i + 1];
-                maybe_black_engine = ExternalEngine::spawn_threaded(path).ok();
+                maybe_black_engine = ExternalEngine::spawn_threaded(path)
+                    .map_err(|e| eprintln!("Failed to start engine `{}`: {}", path, e))
+                    .ok();
+                i += 2;
+            }
+            "movetime" if i + 1 < args.len() => {
+                movetime_ms = args[i + 1].parse().unwrap_or(movetime_ms);
                 i += 2;
             }
+            "match" => {
+                headless = true;
+                i += 1;
+            }
             _ => {
                 i += 1;
             }
         }
     }
 
+    if headless {
+        let (Some(white), Some(black)) = (&maybe_white_engine, &maybe_black_engine) else {
+            eprintln!("A match needs both a white and a black engine");
+            std::process::exit(1);
+        };
+
+        let (_, pgn) =
+            runner::play_game(white, black, Board::default(), movetime_ms, MOVE_TIME_GRACE);
+        println!("{}", pgn);
+        return;
+    }
+
     gui::launch::launch(maybe_white_engine, maybe_black_engine);
 }
+
+fn run_perft(args: &[String]) {
+    let Some(depth) = args
+        .first()
+        .and_then(|d| d.parse::<usize>().ok())
+        .filter(|&d| d > 0)
+    else {
+        eprintln!("Usage: perft <depth> [fen | moves <uci|san>...]");
+        std::process::exit(1);
+    };
+
+    let board = match args.get(1).map(String::as_str) {
+        Some("moves") => {
+            let moves: Vec<&str> = args[2..].iter().map(String::as_str).collect();
+            Board::from_startpos_moves(&moves).unwrap_or_else(|e| {
+                eprintln!("Invalid moves: {}", e);
+                std::process::exit(1);
+            })
+        }
+        Some(fen) => Board::from_fen(fen).unwrap_or_else(|e| {
+            eprintln!("Invalid FEN: {}", e);
+            std::process::exit(1);
+        }),
+        None => Board::default(),
+    };
+
+    let start = Instant::now();
+    let mut total = 0;
+    for (mv, nodes) in board.perft_divide(depth) {
+        println!("{}: {}", mv, nodes);
+        total += nodes;
+    }
+    println!("\nNodes searched: {}", total);
+    println!("Time: {:?}", start.elapsed());
+}
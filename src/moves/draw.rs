@@ -1,67 +1,107 @@
 use std::hash::{DefaultHasher, Hash, Hasher};
 
-use crate::core::{
-    board::{Board, State},
-    piece::PieceType,
-};
+use crate::core::{board::Board, piece::PieceType};
 
 impl Board {
     pub fn has_sufficient_material(&self) -> bool {
-        let mut pieces = vec![];
+        let pieces: Vec<_> = self
+            .pieces()
+            .filter(|(_, p)| p.to_type() != PieceType::King)
+            .map(|((rank, file), p)| (p.to_type(), (rank + file) % 2))
+            .collect();
 
-        for rank in &self.squares {
-            for square in rank {
-                if let Some(piece) = square.piece {
-                    pieces.push(piece);
-                }
-            }
-        }
-
-        match pieces.len() {
-            2 => true,
-            3 => pieces
+        match pieces.as_slice() {
+            [] => false,
+            [(PieceType::Bishop | PieceType::Knight, _)] => false,
+            // Mate with KNN v K or KN v KN only happens if the defender helps, so
+            // they are called drawn even though they aren't strictly dead
+            [(PieceType::Knight, _), (PieceType::Knight, _)] => false,
+            [(_, shade), ..] => !pieces
                 .iter()
-                .any(|p| matches!(p.to_type(), PieceType::Bishop | PieceType::Knight)),
-            4 => {
-                let bishops: Vec<_> = pieces
-                    .iter()
-                    .filter(|p| p.to_type() == PieceType::Bishop)
-                    .collect();
-                bishops.len() == 2 && bishops.iter().all(|b| b.color() != bishops[0].color())
-            }
-            _ => true,
+                .all(|&(pt, s)| pt == PieceType::Bishop && s == *shade),
         }
     }
 
+    pub fn record_position(&mut self) -> usize {
+        let hash = self.compute_position_hash();
+        let count = self.position_history.entry(hash).or_insert(0);
+        *count += 1;
+        *count
+    }
+
     pub fn compute_position_hash(&self) -> u64 {
+        let mut key = self.fen_without_counters();
+        // An en passant square nobody can capture onto doesn't change the position
+        if self.en_passant_target.is_some()
+            && !self.has_en_passant_capture()
+            && let Some((rest, _)) = key.rsplit_once(' ')
+        {
+            key = format!("{} -", rest);
+        }
+
         let mut hasher = DefaultHasher::new();
-        self.hash(&mut hasher);
+        key.hash(&mut hasher);
         hasher.finish()
     }
+
+    fn has_en_passant_capture(&self) -> bool {
+        let Some(target) = self.en_passant_target else {
+            return false;
+        };
+        self.pieces().any(|(from, p)| {
+            p.to_type() == PieceType::Pawn
+                && p.color() == self.side_to_move
+                && from.1.abs_diff(target.1) == 1
+                && self.is_move_legal(from, target, None)
+        })
+    }
 }
 
-impl Hash for Board {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        for rank in 0..8 {
-            for file in 0..8 {
-                if let Some(piece) = self.squares[rank][file].piece {
-                    (rank, file, piece).hash(state);
-                }
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::board::State;
 
-        match self.state {
-            State::Playing { turn } => turn.hash(state),
-            _ => {}
-        }
+    fn sufficient(fen: &str) -> bool {
+        Board::from_fen(fen).unwrap().has_sufficient_material()
+    }
+
+    #[test]
+    fn lone_minor_pieces_cannot_mate() {
+        assert!(!sufficient("4k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+        assert!(!sufficient("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        assert!(!sufficient("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1"));
+        assert!(sufficient("4k3/8/8/8/8/8/8/R3K3 w - - 0 1"));
+        assert!(sufficient("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"));
+    }
+
+    #[test]
+    fn bishops_on_one_shade_cannot_mate() {
+        // c1 and f8 are both dark squares, c1 and c8 are not
+        assert!(!sufficient("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        assert!(sufficient("2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        assert!(sufficient("4k3/8/8/8/8/8/8/1NB1K3 w - - 0 1"));
+    }
+
+    #[test]
+    fn knight_endings_without_pawns_are_drawn() {
+        assert!(!sufficient("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1"));
+        assert!(!sufficient("1n2k3/8/8/8/8/8/8/1N2K3 w - - 0 1"));
+        // A pawn gives the defender something to block its own king with
+        assert!(sufficient("4k3/4p3/8/8/8/8/8/1N2K3 w - - 0 1"));
+        assert!(sufficient("4k3/4p3/8/8/8/8/8/1N2KN2 w - - 0 1"));
+    }
+
+    #[test]
+    fn knight_draws_are_applied_to_the_game() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1").unwrap();
+        assert!(matches!(board.state, State::Draw));
 
-        self.flags.has_white_king_moved.hash(state);
-        self.flags.has_white_kingside_rook_moved.hash(state);
-        self.flags.has_white_queenside_rook_moved.hash(state);
-        self.flags.has_black_king_moved.hash(state);
-        self.flags.has_black_kingside_rook_moved.hash(state);
-        self.flags.has_black_queenside_rook_moved.hash(state);
+        let board = Board::from_fen("4k3/4p3/8/8/8/8/8/1N2KN2 w - - 0 1").unwrap();
+        assert!(matches!(board.state, State::Playing { .. }));
 
-        self.en_passant_target.hash(state);
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/3r4/1N2KN2 w - - 0 1").unwrap();
+        board.push_uci("e1d2").unwrap();
+        assert!(matches!(board.state, State::Draw));
     }
 }
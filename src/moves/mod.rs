@@ -1,4 +1,6 @@
 pub mod draw;
 pub mod moves;
+pub mod notation;
+pub mod perft;
 pub mod rules;
 pub mod simulate;
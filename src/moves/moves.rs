@@ -4,6 +4,16 @@ use crate::core::{
     piece::{PieceKind, PieceType},
 };
 
+use std::fmt;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MoveClass {
+    Normal,
+    Check,
+    Checkmate,
+    Stalemate,
+}
+
 #[derive(Clone)]
 pub struct Move {
     pub from: (usize, usize),
@@ -14,28 +24,36 @@ pub struct Move {
 
 impl Move {
     pub fn to_uci(&self) -> String {
-        let (rank1, file1) = self.from;
-        let (rank2, file2) = self.to;
-
-        let mut s = format!(
-            "{}{}{}{}",
-            (b'a' + file1 as u8) as char,
-            8 - rank1,
-            (b'a' + file2 as u8) as char,
-            8 - rank2,
-        );
+        self.to_string()
+    }
+
+    pub fn is_capture(&self, board: &Board) -> bool {
+        let is_en_passant = self.piece.to_type() == PieceType::Pawn
+            && self.from.1 != self.to.1
+            && board.en_passant_target == Some(self.to);
+        board.piece_at(self.to).is_some() || is_en_passant
+    }
+
+    pub fn is_quiet(&self, board: &Board) -> bool {
+        !self.is_capture(board) && self.promotion.is_none()
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            Board::square_name(self.from),
+            Board::square_name(self.to)
+        )?;
 
         if let Some(promo) = self.promotion {
-            s.push(match promo {
-                PieceType::Queen => 'q',
-                PieceType::Rook => 'r',
-                PieceType::Bishop => 'b',
-                PieceType::Knight => 'n',
-                _ => unreachable!(),
-            });
+            let symbol = PieceKind::new(promo, Color::Black).symbol();
+            write!(f, "{}", symbol)?;
         }
 
-        s
+        Ok(())
     }
 }
 
@@ -159,3 +177,47 @@ impl Board {
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(fen: &str, uci: &str) -> (Board, Move) {
+        let board = Board::from_fen(fen).unwrap();
+        let mv = board.parse_uci_move(uci).unwrap();
+        (board, mv)
+    }
+
+    #[test]
+    fn en_passant_is_a_capture() {
+        let (board, mv) = parse(
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "e5f6",
+        );
+        assert!(mv.is_capture(&board));
+        assert!(!mv.is_quiet(&board));
+
+        // The same diagonal step without an en passant target captures nothing
+        let (board, mv) = parse("4k3/8/8/4P3/8/8/8/4K3 w - - 0 1", "e5f6");
+        assert!(!mv.is_capture(&board));
+    }
+
+    #[test]
+    fn promotion_pushes_are_not_captures() {
+        let (board, mv) = parse("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8q");
+        assert!(!mv.is_capture(&board));
+        assert!(!mv.is_quiet(&board));
+
+        let (board, mv) = parse("4k3/8/8/8/8/8/8/4K3 w - - 0 1", "e1e2");
+        assert!(mv.is_quiet(&board));
+    }
+
+    #[test]
+    fn uci_display() {
+        let (_, mv) = parse("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8n");
+        assert_eq!(mv.to_string(), "a7a8n");
+        assert_eq!(mv.to_uci(), "a7a8n");
+        let (_, mv) = parse("4k3/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1");
+        assert_eq!(mv.to_string(), "e1g1");
+    }
+}
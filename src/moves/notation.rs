@@ -0,0 +1,212 @@
+use crate::{
+    core::{board::Board, piece::PieceType},
+    moves::moves::{Move, MoveClass},
+};
+
+impl Board {
+    pub fn to_san(&self, mv: &Move) -> String {
+        let mut san = self.san_body(mv, true);
+
+        // Only checks can be mate, so the full classification is skipped for quiet moves
+        if self.gives_check(mv) {
+            match self.classify_move(mv) {
+                Some(MoveClass::Checkmate) => san.push('#'),
+                _ => san.push('+'),
+            }
+        }
+
+        san
+    }
+
+    pub fn push_san(&mut self, san: &str) -> Result<Move, String> {
+        let body = san.trim_end_matches(['+', '#', '!', '?']);
+        let legal = self.legal_moves();
+
+        // Input may leave out disambiguation that isn't needed, but never any that is
+        let mut matches: Vec<&Move> = legal
+            .iter()
+            .filter(|mv| self.san_body(mv, true) == body)
+            .collect();
+        if matches.is_empty() {
+            matches = legal
+                .iter()
+                .filter(|mv| self.san_body(mv, false) == body)
+                .collect();
+        }
+
+        let mv = match matches.as_slice() {
+            [mv] => (*mv).clone(),
+            [] => return Err(format!("Illegal move `{}`", san)),
+            _ => return Err(format!("Ambiguous move `{}`", san)),
+        };
+        self.push_uci(&mv.to_uci())
+    }
+
+    fn san_body(&self, mv: &Move, disambiguate: bool) -> String {
+        let piece_type = mv.piece.to_type();
+        let file_delta = mv.to.1 as isize - mv.from.1 as isize;
+
+        if piece_type == PieceType::King && file_delta.abs() == 2 {
+            if file_delta > 0 { "O-O" } else { "O-O-O" }.to_string()
+        } else {
+            let is_capture = mv.is_capture(self);
+
+            let mut san = String::new();
+            if piece_type == PieceType::Pawn {
+                if is_capture {
+                    san.push((b'a' + mv.from.1 as u8) as char);
+                }
+            } else {
+                san.push(mv.piece.symbol().to_ascii_uppercase());
+                if disambiguate {
+                    san.push_str(&self.disambiguation(mv));
+                }
+            }
+
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&Board::square_name(mv.to));
+
+            if let Some(promo) = mv.promotion {
+                san.push('=');
+                san.push(mv.piece.with_type(promo).symbol().to_ascii_uppercase());
+            }
+            san
+        }
+    }
+
+    fn disambiguation(&self, mv: &Move) -> String {
+        // Attack and move geometry agree for every piece that needs disambiguating
+        let others: Vec<_> = self
+            .attackers_to(mv.to, mv.piece.color())
+            .into_iter()
+            .filter(|&pos| {
+                pos != mv.from
+                    && self.piece_at(pos) == Some(mv.piece)
+                    && self.is_move_legal(pos, mv.to, None)
+            })
+            .collect();
+
+        let file = (b'a' + mv.from.1 as u8) as char;
+        let rank = (b'0' + 8 - mv.from.0 as u8) as char;
+        if others.is_empty() {
+            String::new()
+        } else if others.iter().all(|&(_, f)| f != mv.from.1) {
+            file.to_string()
+        } else if others.iter().all(|&(r, _)| r != mv.from.0) {
+            rank.to_string()
+        } else {
+            format!("{file}{rank}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn san_of(fen: &str, uci: &str) -> String {
+        let board = Board::from_fen(fen).unwrap();
+        let mv = board.parse_uci_move(uci).unwrap();
+        board.to_san(&mv)
+    }
+
+    #[test]
+    fn push_san_plays_the_matching_move() {
+        let mut board = Board::default();
+        for san in ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Bxc6", "dxc6", "O-O"] {
+            board.push_san(san).unwrap();
+        }
+        assert_eq!(
+            board.to_fen(),
+            "r1bqkbnr/1pp2ppp/p1p5/4p3/4P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 1 5"
+        );
+    }
+
+    #[test]
+    fn push_san_accepts_annotations_and_promotions() {
+        let mut board = Board::from_fen("8/4P1k1/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let mv = board.push_san("e8=Q!").unwrap();
+        assert_eq!(mv.to_uci(), "e7e8q");
+    }
+
+    #[test]
+    fn push_san_rejects_illegal_moves() {
+        let mut board = Board::default();
+        assert!(board.push_san("e5").is_err());
+        assert!(board.push_san("Ke2").is_err());
+        assert!(board.push_san("O-O").is_err());
+        assert!(board.push_san("nonsense").is_err());
+        assert_eq!(board.to_fen(), Board::default().to_fen());
+    }
+
+    #[test]
+    fn push_san_rejects_ambiguous_moves() {
+        // Knights on b1 and f3 can both reach d2
+        let fen = "4k3/7p/8/8/8/5N2/8/1N2K3 w - - 0 1";
+        let mut board = Board::from_fen(fen).unwrap();
+        assert!(matches!(board.push_san("Nd2"), Err(e) if e.contains("Ambiguous")));
+        assert_eq!(board.to_fen(), fen);
+
+        assert_eq!(board.push_san("Nbd2").unwrap().to_uci(), "b1d2");
+    }
+
+    #[test]
+    fn disambiguation() {
+        let knights = "4k3/7p/8/8/8/5N2/8/1N2K3 w - - 0 1";
+        assert_eq!(san_of(knights, "b1d2"), "Nbd2");
+        assert_eq!(san_of(knights, "f3d2"), "Nfd2");
+
+        let rooks = "4k3/R7/8/8/8/8/8/R3K3 w - - 0 1";
+        assert_eq!(san_of(rooks, "a1a4"), "R1a4");
+        assert_eq!(san_of(rooks, "a7a4"), "R7a4");
+
+        let queens = "6k1/8/8/8/8/Q7/8/Q1Q4K w - - 0 1";
+        assert_eq!(san_of(queens, "a1b2"), "Qa1b2");
+    }
+
+    #[test]
+    fn pinned_pieces_do_not_need_disambiguating() {
+        // The knight on e2 is pinned to the king, so only the b1 knight can reach c3
+        let fen = "4r1k1/8/8/8/8/8/4N3/1N2K3 w - - 0 1";
+        assert_eq!(san_of(fen, "b1c3"), "Nc3");
+
+        let mut board = Board::from_fen(fen).unwrap();
+        assert_eq!(board.push_san("Nc3").unwrap().to_uci(), "b1c3");
+    }
+
+    #[test]
+    fn pinned_rook_is_not_an_alternative() {
+        assert_eq!(san_of("6k1/8/8/8/8/8/R3R3/4K3 w - - 0 1", "a2c2"), "Rac2");
+        // The e2 rook can't leave the e-file while the e8 rook pins it
+        assert_eq!(san_of("4r1k1/8/8/8/8/8/R3R3/4K3 w - - 0 1", "a2c2"), "Rc2");
+    }
+
+    #[test]
+    fn san_marks_captures_castling_and_promotions() {
+        assert_eq!(
+            san_of(
+                "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2",
+                "e4d5"
+            ),
+            "exd5"
+        );
+        assert_eq!(san_of("4k3/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1"), "O-O");
+        assert_eq!(san_of("r3k3/8/8/8/8/8/8/4K3 b q - 0 1", "e8c8"), "O-O-O");
+        assert_eq!(san_of("1r5k/P7/8/8/8/8/8/4K3 w - - 0 1", "a7b8n"), "axb8=N");
+    }
+
+    #[test]
+    fn san_marks_checks_and_mates() {
+        assert_eq!(
+            san_of(
+                "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2",
+                "d8h4"
+            ),
+            "Qh4#"
+        );
+        assert_eq!(san_of("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"), "Ra8+");
+        assert_eq!(san_of("7k/4Q3/6K1/8/8/8/8/8 w - - 0 1", "e7f7"), "Qf7");
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{
+    core::board::{Board, State},
+    moves::moves::Move,
+};
+
+impl Board {
+    pub fn perft(&self, depth: usize) -> u64 {
+        let mut buffers = vec![Vec::new(); depth];
+        self.perft_with(depth, &mut buffers)
+    }
+
+    // One move buffer per ply, reused across every node at that ply
+    fn perft_with(&self, depth: usize, buffers: &mut [Vec<Move>]) -> u64 {
+        let Some((moves, rest)) = buffers.split_first_mut() else {
+            return 1;
+        };
+
+        self.legal_moves_into(moves);
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        moves
+            .iter()
+            .map(|mv| self.after_move(mv).perft_with(depth - 1, rest))
+            .sum()
+    }
+
+    pub fn perft_divide(&self, depth: usize) -> Vec<(Move, u64)> {
+        if depth == 0 {
+            return Vec::new();
+        }
+
+        self.legal_moves()
+            .into_iter()
+            .map(|mv| {
+                let nodes = self.after_move(&mv).perft(depth - 1);
+                (mv, nodes)
+            })
+            .collect()
+    }
+
+    // Only flips the side to move; game-over detection is left to the next generation
+    fn after_move(&self, mv: &Move) -> Board {
+        let mut next = self.clone();
+        let promotion = mv.promotion.map(|pt| mv.piece.with_type(pt));
+        if next.move_piece(mv.from, mv.to, promotion).is_ok() {
+            next.state = State::Playing {
+                turn: mv.piece.color().opponent(),
+            };
+        }
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn perft(fen: &str, depth: usize) -> u64 {
+        Board::from_fen(fen).unwrap().perft(depth)
+    }
+
+    #[test]
+    fn start_position() {
+        let board = Board::default();
+        assert_eq!(board.perft(1), 20);
+        assert_eq!(board.perft(2), 400);
+        assert_eq!(board.perft(3), 8902);
+    }
+
+    #[test]
+    fn standard_positions() {
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        assert_eq!(perft(kiwipete, 1), 48);
+        assert_eq!(perft(kiwipete, 2), 2039);
+        assert_eq!(perft("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 3), 2812);
+        assert_eq!(
+            perft(
+                "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                2
+            ),
+            264
+        );
+        assert_eq!(
+            perft(
+                "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                2
+            ),
+            1486
+        );
+    }
+
+    #[test]
+    fn divide_sums_to_perft() {
+        let board = Board::from_startpos_moves(&["e2e4", "d7d5"]).unwrap();
+        let divided = board.perft_divide(2);
+        assert_eq!(divided.len(), 31);
+        assert_eq!(
+            divided.iter().map(|(_, nodes)| nodes).sum::<u64>(),
+            board.perft(2)
+        );
+    }
+
+    #[test]
+    fn depth_zero() {
+        let board = Board::default();
+        assert_eq!(board.perft(0), 1);
+        assert!(board.perft_divide(0).is_empty());
+        assert_eq!(board.perft_divide(1).len(), 20);
+    }
+}
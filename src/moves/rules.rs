@@ -1,7 +1,10 @@
-use crate::core::{
-    Color,
-    board::Board,
-    piece::{PieceKind, PieceType},
+use crate::{
+    core::{
+        Color,
+        board::{Board, State},
+        piece::{PieceKind, PieceType},
+    },
+    moves::moves::{Move, MoveClass},
 };
 
 impl Board {
@@ -44,11 +47,6 @@ impl Board {
             Color::Black => 1,
         };
 
-        let start_rank = match color {
-            Color::White => 6,
-            Color::Black => 1,
-        };
-
         let dr = tr as isize - fr as isize;
         let df = tf as isize - ff as isize;
 
@@ -56,7 +54,7 @@ impl Board {
             return self.piece_at(to).is_none();
         }
 
-        if df == 0 && fr == start_rank && dr == 2 * dir {
+        if df == 0 && Self::relative_rank(fr, color) == 1 && dr == 2 * dir {
             let mid = ((fr as isize + dir) as usize, ff);
             return self.piece_at(mid).is_none() && self.piece_at(to).is_none();
         }
@@ -67,7 +65,9 @@ impl Board {
             }
 
             if let Some(ep) = self.en_passant_target {
-                return ep == to;
+                let captured = self.piece_at((fr, tf));
+                return ep == to
+                    && captured == Some(PieceKind::new(PieceType::Pawn, color.opponent()));
             }
         }
 
@@ -138,17 +138,19 @@ impl Board {
                 return false;
             }
 
+            let rook = PieceKind::new(PieceType::Rook, color);
+            if self.piece_at((row, 7)) != Some(rook) {
+                return false;
+            }
+
             if self.piece_at((row, 5)).is_some() || self.piece_at((row, 6)).is_some() {
                 return false;
             }
 
             let squares = [(row, 4), (row, 5), (row, 6)];
-            return !squares.iter().any(|&pos| {
-                let mut clone = self.clone();
-                clone.set_piece(pos, self.piece_at((row, 4)));
-                clone.set_piece((row, 4), None);
-                clone.is_in_check(color)
-            });
+            return !squares
+                .iter()
+                .any(|&pos| self.is_square_attacked(pos, color.opponent()));
         }
 
         if ff == 4 && tf == 2 {
@@ -164,6 +166,11 @@ impl Board {
                 return false;
             }
 
+            let rook = PieceKind::new(PieceType::Rook, color);
+            if self.piece_at((row, 0)) != Some(rook) {
+                return false;
+            }
+
             if self.piece_at((row, 3)).is_some()
                 || self.piece_at((row, 2)).is_some()
                 || self.piece_at((row, 1)).is_some()
@@ -172,12 +179,9 @@ impl Board {
             }
 
             let squares = [(row, 4), (row, 3), (row, 2)];
-            return !squares.iter().any(|&pos| {
-                let mut clone = self.clone();
-                clone.set_piece(pos, self.piece_at((row, 4)));
-                clone.set_piece((row, 4), None);
-                clone.is_in_check(color)
-            });
+            return !squares
+                .iter()
+                .any(|&pos| self.is_square_attacked(pos, color.opponent()));
         }
 
         false
@@ -205,70 +209,413 @@ impl Board {
     }
 
     pub fn is_in_check(&self, color: Color) -> bool {
-        let king_pos = match self.find_king(color) {
-            Some(pos) => pos,
-            None => return false,
-        };
+        match self.find_king(color) {
+            Some(king_pos) => self.is_square_attacked(king_pos, color.opponent()),
+            None => false,
+        }
+    }
 
-        for rank in 0..8 {
-            for file in 0..8 {
-                if let Some(attacker) = self.piece_at((rank, file)) {
-                    if attacker.color() != color {
-                        if self.can_attack_square((rank, file), king_pos) {
-                            return true;
-                        }
-                    }
-                }
-            }
+    pub fn checkers(&self, color: Color) -> Vec<(usize, usize)> {
+        match self.find_king(color) {
+            Some(king_pos) => self.attackers_to(king_pos, color.opponent()),
+            None => Vec::new(),
         }
+    }
 
-        false
+    pub fn gives_check(&self, mv: &Move) -> bool {
+        let promotion = mv.promotion.map(|pt| mv.piece.with_type(pt));
+        self.simulate_move(mv.from, mv.to, promotion)
+            .is_ok_and(|after| after.is_in_check(mv.piece.color().opponent()))
     }
 
-    pub fn can_attack_square(&self, from: (usize, usize), to: (usize, usize)) -> bool {
-        if let Some(piece) = self.piece_at(from) {
-            self.is_valid_piece_move(piece, from, to)
-        } else {
-            false
+    pub fn classify_move(&self, mv: &Move) -> Option<MoveClass> {
+        let promotion = mv.promotion.map(|pt| mv.piece.with_type(pt));
+        if !self.is_move_legal(mv.from, mv.to, promotion) {
+            return None;
         }
+
+        let mut after = self.clone();
+        after.move_piece(mv.from, mv.to, promotion).ok()?;
+
+        let opponent = mv.piece.color().opponent();
+        let class = match (after.is_in_check(opponent), after.any_legal_move(opponent)) {
+            (true, true) => MoveClass::Check,
+            (true, false) => MoveClass::Checkmate,
+            (false, false) => MoveClass::Stalemate,
+            (false, true) => MoveClass::Normal,
+        };
+        Some(class)
     }
 
-    pub fn find_king(&self, color: Color) -> Option<(usize, usize)> {
-        for r in 0..8 {
-            for f in 0..8 {
-                if let Some(p) = self.piece_at((r, f)) {
-                    if p.to_type() == PieceType::King && p.color() == color {
-                        return Some((r, f));
-                    }
-                }
+    pub fn attackers_to(&self, pos: (usize, usize), by_color: Color) -> Vec<(usize, usize)> {
+        self.pieces()
+            .filter(|&(from, p)| p.color() == by_color && self.can_attack_square(from, pos))
+            .map(|(from, _)| from)
+            .collect()
+    }
+
+    pub fn is_square_attacked(&self, pos: (usize, usize), by_color: Color) -> bool {
+        self.pieces()
+            .any(|(from, p)| p.color() == by_color && self.can_attack_square(from, pos))
+    }
+
+    pub fn can_attack_square(&self, from: (usize, usize), to: (usize, usize)) -> bool {
+        let Some(piece) = self.piece_at(from) else {
+            return false;
+        };
+        if from == to {
+            return false;
+        }
+
+        let dr = to.0 as isize - from.0 as isize;
+        let df = to.1 as isize - from.1 as isize;
+        match piece.to_type() {
+            PieceType::Pawn => {
+                let dir = match piece.color() {
+                    Color::White => -1,
+                    Color::Black => 1,
+                };
+                dr == dir && df.abs() == 1
             }
+            PieceType::King => dr.abs() <= 1 && df.abs() <= 1,
+            PieceType::Knight => self.validate_knight_move(from, to),
+            PieceType::Bishop => self.validate_bishop_move(from, to),
+            PieceType::Rook => self.validate_rook_move(from, to),
+            PieceType::Queen => self.validate_queen_move(from, to),
         }
-        None
+    }
+
+    pub fn find_king(&self, color: Color) -> Option<(usize, usize)> {
+        self.pieces()
+            .find(|&(_, p)| p.to_type() == PieceType::King && p.color() == color)
+            .map(|(pos, _)| pos)
     }
 
     pub fn any_legal_move(&self, color: Color) -> bool {
-        for from_r in 0..8 {
-            for from_f in 0..8 {
-                let from = (from_r, from_f);
-                if let Some(piece) = self.piece_at(from) {
-                    if piece.color() != color {
-                        continue;
-                    }
-
-                    for to_r in 0..8 {
-                        for to_f in 0..8 {
-                            let to = (to_r, to_f);
-                            if from == to {
-                                continue;
-                            }
-                            if Board::is_valid_pos(to) && self.is_move_legal(from, to, None) {
-                                return true;
-                            }
-                        }
-                    }
+        self.pieces()
+            .filter(|(_, p)| p.color() == color)
+            .any(|(from, piece)| self.legal_targets(from, piece).next().is_some())
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        self.legal_moves_into(&mut moves);
+        moves
+    }
+
+    pub fn legal_moves_into(&self, moves: &mut Vec<Move>) {
+        moves.clear();
+        let State::Playing { turn } = self.state else {
+            return;
+        };
+
+        for (from, piece) in self.pieces().filter(|(_, p)| p.color() == turn) {
+            for (to, promotion) in self.legal_targets(from, piece) {
+                let mv = Move {
+                    from,
+                    to,
+                    promotion: None,
+                    piece,
+                };
+                if promotion.is_none() {
+                    moves.push(mv);
+                    continue;
+                }
+                for pt in [
+                    PieceType::Queen,
+                    PieceType::Rook,
+                    PieceType::Bishop,
+                    PieceType::Knight,
+                ] {
+                    moves.push(Move {
+                        promotion: Some(pt),
+                        ..mv.clone()
+                    });
                 }
             }
         }
-        false
+    }
+
+    pub fn legal_moves_from(&self, from: (usize, usize)) -> Vec<Move> {
+        let Some(piece) = self.piece_at(from) else {
+            return Vec::new();
+        };
+
+        self.legal_targets(from, piece)
+            .map(|(to, promotion)| Move {
+                from,
+                to,
+                promotion: promotion.map(|p| p.to_type()),
+                piece,
+            })
+            .collect()
+    }
+
+    // Shared by every move generator: each legal destination with its probe promotion
+    fn legal_targets(
+        &self,
+        from: (usize, usize),
+        piece: PieceKind,
+    ) -> impl Iterator<Item = ((usize, usize), Option<PieceKind>)> + '_ {
+        (0..8)
+            .flat_map(|rank| (0..8).map(move |file| (rank, file)))
+            .filter(move |&to| to != from)
+            .map(move |to| (to, Self::default_promotion(piece, to)))
+            .filter(move |&(to, promotion)| self.is_move_legal(from, to, promotion))
+    }
+
+    // Pawn moves onto the last rank are only legal with a promotion piece, so
+    // legality probes that don't care about the choice promote to a queen
+    pub fn default_promotion(piece: PieceKind, to: (usize, usize)) -> Option<PieceKind> {
+        if piece.to_type() == PieceType::Pawn && Self::relative_rank(to.0, piece.color()) == 7 {
+            Some(PieceKind::new(PieceType::Queen, piece.color()))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(fen: &str, uci: &str) -> Option<MoveClass> {
+        let board = Board::from_fen(fen).unwrap();
+        let mv = board.parse_uci_move(uci).unwrap();
+        board.classify_move(&mv)
+    }
+
+    #[test]
+    fn fools_mate_is_checkmate() {
+        let board = Board::from_startpos_moves(&["f2f3", "e7e5", "g2g4", "d8h4"]).unwrap();
+        assert!(matches!(
+            board.state,
+            State::Checkmate {
+                winner: Color::Black
+            }
+        ));
+        assert!(board.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn stalemate_after_a_quiet_move() {
+        let mut board = Board::from_fen("7k/4Q3/6K1/8/8/8/8/8 w - - 0 1").unwrap();
+        board.push_uci("e7f7").unwrap();
+        assert!(matches!(board.state, State::Stalemate));
+    }
+
+    #[test]
+    fn promotion_is_an_escape_from_stalemate() {
+        // The king is boxed in, so pushing the pawn to the last rank is the only move
+        let board = Board::from_fen("6r1/1P6/8/k7/8/5n2/8/7K w - - 0 1").unwrap();
+        assert!(matches!(board.state, State::Playing { turn: Color::White }));
+        let moves: Vec<String> = board.legal_moves().iter().map(Move::to_uci).collect();
+        assert_eq!(moves.len(), 4);
+        assert!(moves.iter().all(|uci| uci.starts_with("b7b8")));
+    }
+
+    fn legal_ucis(fen: &str) -> Vec<String> {
+        let board = Board::from_fen(fen).unwrap();
+        board.legal_moves().iter().map(Move::to_uci).collect()
+    }
+
+    #[test]
+    fn en_passant_cannot_uncover_check() {
+        // Both pawns leave the fifth rank, opening it to the rook
+        let moves = legal_ucis("8/8/8/K2pP2r/8/8/8/7k w - d6 0 1");
+        assert!(!moves.contains(&"e5d6".to_string()));
+
+        let moves = legal_ucis("8/8/8/K2pP3/8/8/8/7k w - d6 0 1");
+        assert!(moves.contains(&"e5d6".to_string()));
+    }
+
+    #[test]
+    fn en_passant_needs_a_pawn_to_capture() {
+        let moves = legal_ucis("4k3/8/8/3nP3/8/8/8/4K3 w - d6 0 1");
+        assert!(!moves.contains(&"e5d6".to_string()));
+    }
+
+    #[test]
+    fn castling_needs_the_rook() {
+        let moves = legal_ucis("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        assert!(moves.contains(&"e1g1".to_string()));
+        assert!(moves.contains(&"e1c1".to_string()));
+
+        let moves = legal_ucis("r3k2r/8/8/8/8/8/8/4K2R w KQkq - 0 1");
+        assert!(moves.contains(&"e1g1".to_string()));
+        assert!(!moves.contains(&"e1c1".to_string()));
+    }
+
+    #[test]
+    fn castling_cannot_cross_attacked_squares() {
+        // The f1 square is covered by the rook on f8
+        let moves = legal_ucis("4kr2/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+        assert!(!moves.contains(&"e1g1".to_string()));
+        assert!(moves.contains(&"e1c1".to_string()));
+    }
+
+    fn square(name: &str) -> (usize, usize) {
+        Board::square_from_name(name).unwrap()
+    }
+
+    #[test]
+    fn attackers_to_lists_every_attacker() {
+        let board = Board::from_fen("4k3/8/8/2n5/8/3P4/1B6/3RK3 w - - 0 1").unwrap();
+        let mut white = board.attackers_to(square("e4"), Color::White);
+        white.sort();
+        assert_eq!(white, [square("d3")]);
+
+        let mut white = board.attackers_to(square("d2"), Color::White);
+        white.sort();
+        let mut expected = [square("d1"), square("e1")];
+        expected.sort();
+        assert_eq!(white, expected);
+
+        assert_eq!(
+            board.attackers_to(square("e4"), Color::Black),
+            [square("c5")]
+        );
+        // The d3 pawn blocks the rook, and pawns only attack diagonally
+        assert_eq!(
+            board.attackers_to(square("d4"), Color::White),
+            [square("b2")]
+        );
+    }
+
+    #[test]
+    fn square_attacks_respect_blockers_and_pawn_direction() {
+        let board = Board::from_fen("4k3/8/8/8/3p4/8/8/R3K3 w - - 0 1").unwrap();
+        assert!(board.is_square_attacked(square("a8"), Color::White));
+        assert!(board.is_square_attacked(square("c3"), Color::Black));
+        assert!(board.is_square_attacked(square("e3"), Color::Black));
+        assert!(!board.is_square_attacked(square("d3"), Color::Black));
+
+        // The e1 king shields the rest of the first rank from the rook
+        assert!(board.is_square_attacked(square("d1"), Color::White));
+        assert!(!board.is_square_attacked(square("h1"), Color::White));
+    }
+
+    #[test]
+    fn minor_piece_and_queen_attacks() {
+        let board = Board::from_fen("4k3/8/8/3q4/4p3/8/1N4B1/4K3 w - - 0 1").unwrap();
+        assert!(board.is_square_attacked(square("d3"), Color::White));
+        assert!(board.is_square_attacked(square("c4"), Color::White));
+        assert!(!board.is_square_attacked(square("b3"), Color::White));
+
+        // The e4 pawn stops the bishop's diagonal and the queen's
+        assert!(board.is_square_attacked(square("f3"), Color::White));
+        assert!(!board.is_square_attacked(square("d5"), Color::White));
+        assert!(board.is_square_attacked(square("a8"), Color::Black));
+        assert!(board.is_square_attacked(square("d1"), Color::Black));
+        assert!(!board.is_square_attacked(square("h1"), Color::Black));
+    }
+
+    fn gives_check(fen: &str, uci: &str) -> bool {
+        let board = Board::from_fen(fen).unwrap();
+        let mv = board.parse_uci_move(uci).unwrap();
+        board.gives_check(&mv)
+    }
+
+    #[test]
+    fn direct_and_discovered_checks() {
+        assert!(gives_check("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"));
+        assert!(!gives_check("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a7"));
+        // The bishop moving off the e-file uncovers the rook
+        assert!(gives_check("4k3/8/8/8/8/4B3/8/4RK2 w - - 0 1", "e3c5"));
+    }
+
+    #[test]
+    fn double_check() {
+        // The knight checks from d6 and uncovers the rook behind it
+        let fen = "4k3/8/8/8/4N3/8/8/4RK2 w - - 0 1";
+        assert!(gives_check(fen, "e4d6"));
+
+        let mut board = Board::from_fen(fen).unwrap();
+        board.push_uci("e4d6").unwrap();
+        assert_eq!(board.checkers(Color::Black).len(), 2);
+    }
+
+    #[test]
+    fn special_moves_can_give_check() {
+        // The castled rook lands on f1, facing the king on f8
+        assert!(gives_check("5k2/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1"));
+        assert!(gives_check("3k4/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8q"));
+        assert!(!gives_check("3k4/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8n"));
+        // Capturing en passant clears the fifth rank between rook and king
+        assert!(gives_check("8/8/8/R2pP2k/8/8/8/4K3 w - d6 0 1", "e5d6"));
+    }
+
+    #[test]
+    fn checkers_finds_every_checking_piece() {
+        assert!(Board::default().checkers(Color::White).is_empty());
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 b - - 0 1").unwrap();
+        assert!(board.checkers(Color::Black).is_empty());
+
+        let slider = Board::from_fen("4k3/8/8/8/8/8/8/4RK2 b - - 0 1").unwrap();
+        assert_eq!(slider.checkers(Color::Black), [square("e1")]);
+
+        let knight = Board::from_fen("4k3/8/3N4/8/8/8/8/5K2 b - - 0 1").unwrap();
+        assert_eq!(knight.checkers(Color::Black), [square("d6")]);
+
+        // Double check from the rook and the knight
+        let board = Board::from_fen("4k3/8/3N4/8/8/8/8/4RK2 b - - 0 1").unwrap();
+        let mut checkers = board.checkers(Color::Black);
+        checkers.sort();
+        let mut expected = [square("d6"), square("e1")];
+        expected.sort();
+        assert_eq!(checkers, expected);
+        assert!(
+            board
+                .legal_moves()
+                .iter()
+                .all(|mv| mv.piece.to_type() == PieceType::King)
+        );
+    }
+
+    #[test]
+    fn move_buffers_are_cleared_and_refilled() {
+        let mut buffer = Vec::new();
+        let start = Board::default();
+        start.legal_moves_into(&mut buffer);
+        assert_eq!(buffer.len(), 20);
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+        board.legal_moves_into(&mut buffer);
+        let into: Vec<String> = buffer.iter().map(Move::to_uci).collect();
+        let fresh: Vec<String> = board.legal_moves().iter().map(Move::to_uci).collect();
+        assert_eq!(into, fresh);
+
+        let mated = Board::from_startpos_moves(&["f2f3", "e7e5", "g2g4", "d8h4"]).unwrap();
+        mated.legal_moves_into(&mut buffer);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn classify_terminal_moves() {
+        let fools_mate = "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2";
+        assert_eq!(classify(fools_mate, "d8h4"), Some(MoveClass::Checkmate));
+        assert_eq!(classify(fools_mate, "d8e7"), Some(MoveClass::Normal));
+
+        let rook = "4k3/8/8/8/8/8/8/R3K3 w - - 0 1";
+        assert_eq!(classify(rook, "a1a8"), Some(MoveClass::Check));
+
+        let queen = "7k/4Q3/6K1/8/8/8/8/8 w - - 0 1";
+        assert_eq!(classify(queen, "e7f7"), Some(MoveClass::Stalemate));
+        assert_eq!(classify(queen, "e7a7"), Some(MoveClass::Normal));
+    }
+
+    #[test]
+    fn classify_rejects_illegal_moves() {
+        // The e2 knight is pinned against the king
+        let board = Board::from_fen("4r1k1/8/8/8/8/8/4N3/4K3 w - - 0 1").unwrap();
+        let pinned = Move {
+            from: (6, 4),
+            to: (4, 3),
+            promotion: None,
+            piece: board.piece_at((6, 4)).unwrap(),
+        };
+        assert_eq!(board.classify_move(&pinned), None);
+        assert!(!board.gives_check(&pinned));
     }
 }
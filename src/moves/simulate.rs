@@ -1,5 +1,4 @@
 use crate::core::{
-    Color,
     board::Board,
     piece::{PieceKind, PieceType},
 };
@@ -11,45 +10,40 @@ impl Board {
         to: (usize, usize),
         promotion: Option<PieceKind>,
     ) -> Result<Board, String> {
-        let mut clone = self.clone();
-        let piece = clone.piece_at(from).ok_or("No piece at from")?;
+        let piece = self.piece_at(from).ok_or("No piece at from")?;
 
         if !Board::is_valid_pos(to) {
             return Err("Invalid target position".into());
         }
 
-        if !clone.is_valid_piece_move(piece, from, to) {
+        if !self.is_valid_piece_move(piece, from, to) {
             return Err("Invalid piece move".into());
         }
 
-        if let Some(target) = clone.piece_at(to) {
+        if let Some(target) = self.piece_at(to) {
             if target.color() == piece.color() {
                 return Err("Can't capture own piece".into());
             }
         }
 
+        let mut clone = self.clone();
+        clone.handle_en_passant(from, to, piece);
+        clone.handle_castling(from, to);
         clone.set_piece(to, Some(piece));
         clone.set_piece(from, None);
 
-        if piece.to_type() == PieceType::Pawn {
-            let promotion_rank = match piece.color() {
-                Color::White => 0,
-                Color::Black => 7,
+        if piece.to_type() == PieceType::Pawn && Board::relative_rank(to.0, piece.color()) == 7 {
+            let promo_piece = match promotion {
+                Some(p) => p,
+                None => return Err("Promotion piece not specified".into()),
             };
 
-            if to.0 == promotion_rank {
-                let promo_piece = match promotion {
-                    Some(p) => p,
-                    None => return Err("Promotion piece not specified".into()),
-                };
-
-                match promo_piece.to_type() {
-                    PieceType::Queen | PieceType::Rook | PieceType::Bishop | PieceType::Knight => {}
-                    _ => return Err("Invalid promotion piece".into()),
-                }
-
-                clone.set_piece(to, Some(promo_piece));
+            match promo_piece.to_type() {
+                PieceType::Queen | PieceType::Rook | PieceType::Bishop | PieceType::Knight => {}
+                _ => return Err("Invalid promotion piece".into()),
             }
+
+            clone.set_piece(to, Some(promo_piece));
         }
 
         Ok(clone)
@@ -69,3 +63,60 @@ impl Board {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Color;
+
+    fn square(name: &str) -> (usize, usize) {
+        Board::square_from_name(name).unwrap()
+    }
+
+    #[test]
+    fn simulation_leaves_the_board_untouched() {
+        let board = Board::default();
+        let after = board
+            .simulate_move(square("g1"), square("f3"), None)
+            .unwrap();
+        assert!(after.piece_at(square("g1")).is_none());
+        assert_eq!(
+            after.piece_at(square("f3")),
+            Some(PieceKind::new(PieceType::Knight, Color::White))
+        );
+        assert_eq!(board.to_fen(), Board::default().to_fen());
+    }
+
+    #[test]
+    fn invalid_moves_are_rejected() {
+        let board = Board::default();
+        assert!(
+            board
+                .simulate_move(square("e4"), square("e5"), None)
+                .is_err()
+        );
+        assert!(
+            board
+                .simulate_move(square("g1"), square("g3"), None)
+                .is_err()
+        );
+        assert!(
+            board
+                .simulate_move(square("d1"), square("d2"), None)
+                .is_err()
+        );
+
+        let promotion = Board::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(
+            promotion
+                .simulate_move(square("a7"), square("a8"), None)
+                .is_err()
+        );
+        let king = Some(PieceKind::new(PieceType::King, Color::White));
+        assert!(
+            promotion
+                .simulate_move(square("a7"), square("a8"), king)
+                .is_err()
+        );
+    }
+}